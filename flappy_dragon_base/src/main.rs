@@ -3,6 +3,8 @@ use my_library::*;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Default, States, Copy)]
 enum GamePhase {
+    Loading,
+    LoadFailed,
     #[default]
     MainMenu,
     Flapping,
@@ -23,6 +25,23 @@ struct FlappyElement;
 #[derive(Component)]
 struct Obstacle; //(3)
 
+// wall gaps follow noise seeded from their own stream, so other random
+// effects don't change the wall layout for a given seed, and each gap
+// is close to the last one
+#[derive(Resource)]
+struct WallGaps {
+    noise: Perlin,
+    walls: u32,
+}
+
+impl WallGaps {
+    fn next_gap(&mut self) -> i32 {
+        self.walls += 1;
+        let height = self.noise.sample_1d(self.walls as f32 * 0.3);
+        ((height * 5.0).round() as i32).clamp(-5, 4)
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let mut app = App::new();
     add_phase!(app, GamePhase, GamePhase::Flapping, start => [setup], run => [gravity, flap, clamp, move_walls, hit_wall], exit => [cleanup::<FlappyElement>]);
@@ -35,28 +54,30 @@ fn main() -> anyhow::Result<()> {
         }),
         ..default()
     }))
-    .add_plugins(RandomPlugin)
-    .add_plugins(GameStatePlugin::new(
-        GamePhase::MainMenu,
-        GamePhase::Flapping,
-        GamePhase::GameOver,
-    ))
+    .add_plugins(RandomPlugin::from_env("GAME_SEED"))
+    .add_systems(Startup, fork_wall_gaps)
     .add_plugins(
-        AssetManager::new()
-            .add_image("dragon", "flappy_dragon.png")?
-            .add_image("wall", "wall.png")?,
+        GameStatePlugin::new(
+            GamePhase::MainMenu,
+            GamePhase::Flapping,
+            GamePhase::GameOver,
+        )
+        .with_loading(GamePhase::Loading, GamePhase::LoadFailed),
     )
+    .add_plugins(AssetManager::from_manifest("assets/manifest.ron")?)
     .run();
     Ok(())
 }
 
-fn setup(
-    mut commands: Commands,
-    rng: ResMut<RandomNumberGenerator>,
-    assets: Res<AssetStore>,
-    loaded_assets: AssetResource,
-) {
-    commands.spawn((Camera2d::default(), FlappyElement));
+fn fork_wall_gaps(mut commands: Commands, rng: Res<RandomNumberGenerator>) {
+    commands.insert_resource(WallGaps {
+        noise: Perlin::new(&mut rng.fork("walls")),
+        walls: 0,
+    });
+}
+
+fn setup(mut commands: Commands, mut gaps: ResMut<WallGaps>, assets: Res<AssetStore>) {
+    commands.spawn((Camera2d, FlappyElement));
     spawn_image!(
         assets,
         commands,
@@ -64,19 +85,13 @@ fn setup(
         -490.0,
         0.0,
         1.0,
-        &loaded_assets,
         Flappy { gravity: 0.0 },
         FlappyElement
     );
-    build_wall(&mut commands, &assets, &loaded_assets, rng.range(-5..5));
+    build_wall(&mut commands, &assets, gaps.next_gap());
 }
 
-fn build_wall(
-    commands: &mut Commands,
-    assets: &AssetStore,
-    loaded_assets: &LoadedAssets,
-    gap_y: i32,
-) {
+fn build_wall(commands: &mut Commands, assets: &AssetStore, gap_y: i32) {
     for y in -12..=12 {
         if y < gap_y - 4 || y > gap_y + 4 {
             spawn_image!(
@@ -86,7 +101,6 @@ fn build_wall(
                 512.0,
                 y as f32 * 32.0,
                 1.0,
-                &loaded_assets,
                 Obstacle,
                 FlappyElement
             );
@@ -125,8 +139,7 @@ fn move_walls(
     mut query: Query<&mut Transform, With<Obstacle>>,
     delete: Query<Entity, With<Obstacle>>,
     assets: Res<AssetStore>,
-    loaded_assets: AssetResource,
-    rng: ResMut<RandomNumberGenerator>,
+    mut gaps: ResMut<WallGaps>,
 ) {
     let mut rebuild = false;
     for mut transform in query.iter_mut() {
@@ -139,7 +152,7 @@ fn move_walls(
         for entity in delete.iter() {
             commands.entity(entity).despawn();
         }
-        build_wall(&mut commands, &assets, &&loaded_assets, rng.range(-5..5));
+        build_wall(&mut commands, &assets, gaps.next_gap());
     }
 }
 
@@ -4,7 +4,7 @@ use bevy::prelude::*;
 struct Dragon;
 
 fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn(Camera2d::default());
+    commands.spawn(Camera2d);
     let dragon_image = asset_server.load("dragon.png");
     commands
         .spawn(Sprite::from_image(dragon_image))
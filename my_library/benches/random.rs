@@ -3,6 +3,8 @@ use my_library::*;
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("random", |b| {
+        // only needs to be mutable without the `locking` feature
+        #[allow(unused_mut)]
         let mut rng = RandomNumberGenerator::new();
         b.iter(|| {
             rng.range(1.0_f32..10_000_000_f32);
@@ -10,5 +12,78 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+/// Compares every algorithm, regardless of which one the features default to.
+pub fn algorithm_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("algorithms");
+    for algorithm in Algorithm::ALL {
+        // only needs to be mutable without the `locking` feature
+        #[allow(unused_mut)]
+        let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 1);
+        group.bench_function(format!("{algorithm:?}"), |b| {
+            b.iter(|| {
+                rng.range(1.0_f32..10_000_000_f32);
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Compares drawing from the `locking` generator's own stream, where every
+/// thread waits on the same lock, with drawing from its named streams (what
+/// a `SystemRng` hands each system), both on their own and with several
+/// threads drawing at once.
+#[cfg(feature = "locking")]
+mod contention {
+    use super::*;
+
+    const THREADS: usize = 4;
+    const DRAWS: usize = 10_000;
+
+    /// Runs `draw` on several threads, passing each its thread number.
+    fn draw_in_parallel(draw: impl Fn(usize) -> f32 + Sync) {
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let draw = &draw;
+                scope.spawn(move || {
+                    for _ in 0..DRAWS {
+                        std::hint::black_box(draw(thread));
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn bench(c: &mut Criterion) {
+        let rng = RandomNumberGenerator::seeded(1);
+        let streams: Vec<RngStream> = (0..THREADS)
+            .map(|thread| rng.stream(&format!("system {thread}")))
+            .collect();
+
+        let mut group = c.benchmark_group("uncontended");
+        group.bench_function("shared", |b| b.iter(|| rng.range(1.0_f32..10_000_000_f32)));
+        group.bench_function("streams", |b| {
+            b.iter(|| (&streams[0]).range(1.0_f32..10_000_000_f32))
+        });
+        group.finish();
+
+        let mut group = c.benchmark_group("contended");
+        group.bench_function("shared", |b| {
+            b.iter(|| draw_in_parallel(|_| rng.range(1.0_f32..10_000_000_f32)))
+        });
+        group.bench_function("streams", |b| {
+            b.iter(|| draw_in_parallel(|thread| (&streams[thread]).range(1.0_f32..10_000_000_f32)))
+        });
+        group.finish();
+    }
+}
+
+#[cfg(not(feature = "locking"))]
+criterion_group!(benches, criterion_benchmark, algorithm_benchmark);
+#[cfg(feature = "locking")]
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    algorithm_benchmark,
+    contention::bench
+);
 criterion_main!(benches);
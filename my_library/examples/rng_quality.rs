@@ -0,0 +1,105 @@
+//! Run the statistical quality checks and a speed test on every generator
+//! algorithm, and print the results as a Markdown table.
+//!
+//! The `locking` feature is chosen at compile time, so compare locking and
+//! non-locking builds by running twice and appending to the same file:
+//!
+//! ```text
+//! cargo run --release --example rng_quality -- --append rng_quality.md
+//! cargo run --release --example rng_quality --features locking -- --append rng_quality.md
+//! ```
+//!
+//! Options: `--samples N` draws per check (default 1,000,000), `--seed N`
+//! (default 1), `--append FILE` also appends the table rows to `FILE`.
+use my_library::{
+    Algorithm, LARGE_SPAN, QualityReport, RandomNumberGenerator, SMALL_SPAN, Z_LIMIT,
+};
+use std::{fs::OpenOptions, hint::black_box, io::Write, path::PathBuf, time::Instant};
+
+const HEADER: &str = "| build | algorithm | ns/draw | chi² z | serial z | runs z | \
+                      d6 bias | large bias | verdict |\n\
+                      |---|---|---:|---:|---:|---:|---:|---:|---|";
+
+struct Options {
+    samples: usize,
+    seed: u64,
+    append: Option<PathBuf>,
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        samples: 1_000_000,
+        seed: 1,
+        append: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{arg} needs a value"));
+        match arg.as_str() {
+            "--samples" => options.samples = value().parse().expect("--samples takes a number"),
+            "--seed" => options.seed = value().parse().expect("--seed takes a number"),
+            "--append" => options.append = Some(value().into()),
+            _ => panic!("unknown option {arg}; expected --samples, --seed or --append"),
+        }
+    }
+    options
+}
+
+/// Average time of one `u64` draw, in nanoseconds.
+fn time_draws(rng: &mut RandomNumberGenerator, draws: usize) -> f64 {
+    let start = Instant::now();
+    for _ in 0..draws {
+        black_box(rng.next::<u64>());
+    }
+    start.elapsed().as_nanos() as f64 / draws as f64
+}
+
+fn main() {
+    let options = parse_options();
+    let build = if cfg!(feature = "locking") {
+        "locking"
+    } else {
+        "plain"
+    };
+
+    let rows: Vec<String> = Algorithm::ALL
+        .into_iter()
+        .map(|algorithm| {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, options.seed);
+            let nanos = time_draws(&mut rng, options.samples);
+            let report = QualityReport::measure(&mut rng, options.samples);
+            format!(
+                "| {build} | {algorithm:?} | {nanos:.2} | {:.2} | {:.2} | {:.2} | {:.3}% | {:.3}% | {} |",
+                report.chi_square_z,
+                report.serial_correlation_z,
+                report.runs_z,
+                report.small_range_bias * 100.0,
+                report.large_range_bias * 100.0,
+                if report.passes() { "pass" } else { "FAIL" },
+            )
+        })
+        .collect();
+
+    println!("{HEADER}");
+    rows.iter().for_each(|row| println!("{row}"));
+    println!();
+    println!(
+        "{} samples per check, seed {}. z-scores beyond ±{Z_LIMIT} fail; biases are the worst \
+         relative error of range(0..{SMALL_SPAN}) and range(0..{LARGE_SPAN}).",
+        options.samples, options.seed
+    );
+
+    if let Some(path) = options.append {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .unwrap_or_else(|err| panic!("can't open {}: {err}", path.display()));
+        let empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+        if empty {
+            writeln!(file, "{HEADER}").unwrap();
+        }
+        rows.iter().for_each(|row| writeln!(file, "{row}").unwrap());
+        println!("Appended to {}", path.display());
+    }
+}
@@ -0,0 +1,147 @@
+//! Runtime choice of random number generation algorithm.
+use rand::SeedableRng;
+
+/// The random number generation algorithms a
+/// [`RandomNumberGenerator`](crate::RandomNumberGenerator) can use.
+///
+/// Every algorithm is always available; the `pcg` and `xorshift` features
+/// only pick which one [`Algorithm::default`] (and so
+/// [`RandomNumberGenerator::new`](crate::RandomNumberGenerator::new) and
+/// [`RandomNumberGenerator::seeded`](crate::RandomNumberGenerator::seeded))
+/// uses.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{Algorithm, RandomNumberGenerator};
+/// for algorithm in Algorithm::ALL {
+///     let rng = RandomNumberGenerator::with_algorithm(algorithm, 1);
+///     assert_eq!(rng.algorithm(), algorithm);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Algorithm {
+    /// ChaCha12, the algorithm behind the `rand` crate's `StdRng`.
+    ChaCha12,
+    /// XorShift: very fast, but with weaker statistical quality.
+    XorShift,
+    /// PCG (64-bit output, 128-bit state): fast and of good quality.
+    Pcg64,
+}
+
+impl Algorithm {
+    /// Every supported algorithm.
+    pub const ALL: [Algorithm; 3] = [Algorithm::ChaCha12, Algorithm::XorShift, Algorithm::Pcg64];
+}
+
+impl Default for Algorithm {
+    // library users can pick the default algo with a feature
+    fn default() -> Self {
+        if cfg!(feature = "xorshift") {
+            Algorithm::XorShift
+        } else if cfg!(feature = "pcg") {
+            Algorithm::Pcg64
+        } else {
+            Algorithm::ChaCha12
+        }
+    }
+}
+
+// One generator of any algorithm, dispatched with a `match`.
+// Generators are long-lived, so ChaCha's larger state isn't boxed.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum AlgorithmRng {
+    // same algorithm as `rand::prelude::StdRng`, which can't be serialized
+    ChaCha12(rand_chacha::ChaCha12Rng),
+    XorShift(rand_xorshift::XorShiftRng),
+    Pcg64(rand_pcg::Pcg64),
+}
+
+impl AlgorithmRng {
+    pub(crate) fn seeded(algorithm: Algorithm, seed: u64) -> Self {
+        match algorithm {
+            Algorithm::ChaCha12 => Self::ChaCha12(SeedableRng::seed_from_u64(seed)),
+            Algorithm::XorShift => Self::XorShift(SeedableRng::seed_from_u64(seed)),
+            Algorithm::Pcg64 => Self::Pcg64(SeedableRng::seed_from_u64(seed)),
+        }
+    }
+
+    pub(crate) fn algorithm(&self) -> Algorithm {
+        match self {
+            Self::ChaCha12(_) => Algorithm::ChaCha12,
+            Self::XorShift(_) => Algorithm::XorShift,
+            Self::Pcg64(_) => Algorithm::Pcg64,
+        }
+    }
+}
+
+impl rand::RngCore for AlgorithmRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            Self::ChaCha12(rng) => rng.next_u32(),
+            Self::XorShift(rng) => rng.next_u32(),
+            Self::Pcg64(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            Self::ChaCha12(rng) => rng.next_u64(),
+            Self::XorShift(rng) => rng.next_u64(),
+            Self::Pcg64(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            Self::ChaCha12(rng) => rng.fill_bytes(dest),
+            Self::XorShift(rng) => rng.fill_bytes(dest),
+            Self::Pcg64(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            Self::ChaCha12(rng) => rng.try_fill_bytes(dest),
+            Self::XorShift(rng) => rng.try_fill_bytes(dest),
+            Self::Pcg64(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_matches_backend() {
+        // dispatch must not change what each backend produces
+        let mut rng = AlgorithmRng::seeded(Algorithm::ChaCha12, 1);
+        let mut backend = rand_chacha::ChaCha12Rng::seed_from_u64(1);
+        (0..100).for_each(|_| assert_eq!(rng.r#gen::<u64>(), backend.r#gen::<u64>()));
+        let mut rng = AlgorithmRng::seeded(Algorithm::XorShift, 1);
+        let mut backend = rand_xorshift::XorShiftRng::seed_from_u64(1);
+        (0..100).for_each(|_| assert_eq!(rng.r#gen::<u64>(), backend.r#gen::<u64>()));
+        let mut rng = AlgorithmRng::seeded(Algorithm::Pcg64, 1);
+        let mut backend = rand_pcg::Pcg64::seed_from_u64(1);
+        (0..100).for_each(|_| assert_eq!(rng.r#gen::<u64>(), backend.r#gen::<u64>()));
+    }
+
+    #[test]
+    fn test_algorithms_differ() {
+        let draws: Vec<u64> = Algorithm::ALL
+            .iter()
+            .map(|algorithm| AlgorithmRng::seeded(*algorithm, 1).r#gen())
+            .collect();
+        assert_ne!(draws[0], draws[1]);
+        assert_ne!(draws[1], draws[2]);
+        assert_ne!(draws[0], draws[2]);
+        for algorithm in Algorithm::ALL {
+            assert_eq!(AlgorithmRng::seeded(algorithm, 1).algorithm(), algorithm);
+        }
+    }
+}
@@ -1,46 +1,214 @@
-use crate::AssetStore;
+use crate::{AssetEntry, AssetStore, SpriteSheet, WordList, WordListLoader};
 use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use {bevy::asset::io::file::FileAssetReader, std::path::PathBuf};
 
-#[derive(Clone)]
+/// The kind of asset a file is loaded as.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AssetType {
     Image,
+    Sound,
+    Font,
+    /// An image cut into a grid of equally sized sprites.
+    SpriteSheet {
+        tile_size: UVec2,
+        columns: u32,
+        rows: u32,
+    },
+    WordList,
 }
 
-#[derive(Resource, Clone)]
+impl AssetType {
+    /// The file extensions Bevy, with its default features, can load as
+    /// this type of asset. Others can be allowed with
+    /// [`AssetManager::allow_extension`].
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            AssetType::Image | AssetType::SpriteSheet { .. } => &["png", "hdr", "ktx2"],
+            AssetType::Sound => &["ogg", "oga", "spx"],
+            AssetType::Font => &["ttf", "otf"],
+            AssetType::WordList => &["names"],
+        }
+    }
+
+    /// Whether the file is an image; sprite sheets are images too.
+    pub(super) fn is_image(&self) -> bool {
+        matches!(self, AssetType::Image | AssetType::SpriteSheet { .. })
+    }
+
+    /// Whether `self` and `other` are loaded from the same kind of file.
+    fn same_files(&self, other: &AssetType) -> bool {
+        (self.is_image() && other.is_image())
+            || std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// An asset registered with an [`AssetManager`].
+#[derive(Clone, Debug)]
+pub(super) struct ListedAsset {
+    pub(super) tag: String,
+    pub(super) filename: String,
+    pub(super) asset_type: AssetType,
+    /// Where it was registered, such as a manifest line, for error reports.
+    pub(super) origin: Option<String>,
+}
+
+/// Adding the plugin validates the assets (see
+/// [`validate`](AssetManager::validate)), and panics with the report if any
+/// have problems, so a missing file is caught even if `validate` wasn't
+/// called. A manager `validate` already passed isn't checked twice.
+#[derive(Resource, Clone, Debug)]
 pub struct AssetManager {
-    // asset tag, filename and type
-    asset_list: Vec<(String, String, AssetType)>,
+    pub(super) asset_list: Vec<ListedAsset>,
+    // the assets directory, relative to the base path, as in `AssetPlugin`
+    file_path: String,
+    // extensions allowed on top of `AssetType::extensions`, in lower case
+    extra_extensions: Vec<(AssetType, String)>,
+    // passed `validate`, and unchanged since
+    pub(super) validated: bool,
+}
+
+impl Default for AssetManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AssetManager {
     pub fn new() -> Self {
         Self {
             asset_list: Vec::new(),
+            file_path: AssetPlugin::default().file_path,
+            extra_extensions: Vec::new(),
+            validated: false,
         }
     }
-    pub fn add_image<S: ToString>(mut self, tag: S, filename: S) -> anyhow::Result<Self> {
-        let filename = filename.to_string();
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            let current_directory = std::env::current_dir()?;
-            let assets = current_directory.join("assets");
-            let new_image = assets.join(&filename);
-            if !new_image.exists() {
-                return Err(anyhow::Error::msg(format!(
-                    "{} not found in assets directory",
-                    &filename
-                )));
-            }
-        }
-        self.asset_list
-            .push((tag.to_string(), filename, AssetType::Image));
-        Ok(self)
+
+    /// Sets the directory assets are loaded from. This must match the
+    /// `file_path` given to Bevy's `AssetPlugin`, and defaults to the same
+    /// `assets`.
+    pub fn file_path<S: ToString>(mut self, file_path: S) -> Self {
+        self.file_path = file_path.to_string();
+        self.validated = false;
+        self
+    }
+
+    /// The directory the assets are checked in, found the way Bevy's
+    /// `AssetPlugin` finds it: `file_path` inside `BEVY_ASSET_ROOT` if that
+    /// is set, else inside `CARGO_MANIFEST_DIR` (set by `cargo run`), else
+    /// next to the executable. The working directory plays no part.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn asset_root(&self) -> PathBuf {
+        FileAssetReader::get_base_path().join(&self.file_path)
+    }
+
+    /// Accepts files ending in `extension` as `asset_type` when validating,
+    /// for formats enabled through Bevy features (such as `jpeg` or `ktx2`
+    /// images) or handled by loaders of your own. Extensions allowed for
+    /// images also apply to sprite sheets. Call this before
+    /// [`add_manifest`](AssetManager::add_manifest), which validates.
+    pub fn allow_extension<S: ToString>(mut self, asset_type: AssetType, extension: S) -> Self {
+        let extension = extension.to_string().to_ascii_lowercase();
+        self.extra_extensions.push((asset_type, extension));
+        self.validated = false;
+        self
+    }
+
+    /// The extensions, in lower case, accepted for `asset_type`.
+    pub(super) fn extensions(&self, asset_type: &AssetType) -> Vec<String> {
+        let extra = self
+            .extra_extensions
+            .iter()
+            .filter(|(allowed, _)| allowed.same_files(asset_type))
+            .map(|(_, extension)| extension.clone());
+        asset_type
+            .extensions()
+            .iter()
+            .map(|extension| extension.to_string())
+            .chain(extra)
+            .collect()
+    }
+
+    pub fn add_image<S: ToString>(self, tag: S, filename: S) -> Self {
+        self.add(
+            tag.to_string(),
+            filename.to_string(),
+            AssetType::Image,
+            None,
+        )
+    }
+
+    /// Adds a sound effect or music track, loaded as an `AudioSource`.
+    pub fn add_sound<S: ToString>(self, tag: S, filename: S) -> Self {
+        self.add(
+            tag.to_string(),
+            filename.to_string(),
+            AssetType::Sound,
+            None,
+        )
+    }
+
+    /// Adds a font, for `TextFont`.
+    pub fn add_font<S: ToString>(self, tag: S, filename: S) -> Self {
+        self.add(tag.to_string(), filename.to_string(), AssetType::Font, None)
+    }
+
+    /// Adds an image made of `columns` by `rows` sprites of `tile_size`
+    /// pixels each. The texture atlas layout is built along with the image,
+    /// and both are available from [`AssetStore::sprite_sheet`].
+    pub fn add_sprite_sheet<S: ToString>(
+        self,
+        tag: S,
+        filename: S,
+        tile_size: UVec2,
+        columns: u32,
+        rows: u32,
+    ) -> Self {
+        let sheet = AssetType::SpriteSheet {
+            tile_size,
+            columns,
+            rows,
+        };
+        self.add(tag.to_string(), filename.to_string(), sheet, None)
+    }
+
+    /// Adds a `.names` file of training words for a
+    /// [`NameGenerator`](crate::NameGenerator), loaded as a [`WordList`].
+    pub fn add_word_list<S: ToString>(self, tag: S, filename: S) -> Self {
+        self.add(
+            tag.to_string(),
+            filename.to_string(),
+            AssetType::WordList,
+            None,
+        )
+    }
+
+    pub(super) fn add(
+        mut self,
+        tag: String,
+        filename: String,
+        asset_type: AssetType,
+        origin: Option<String>,
+    ) -> Self {
+        self.asset_list.push(ListedAsset {
+            tag,
+            filename,
+            asset_type,
+            origin,
+        });
+        self.validated = false;
+        self
     }
 }
 
 impl Plugin for AssetManager {
     fn build(&self, app: &mut bevy::prelude::App) {
+        if !self.validated {
+            self.check().unwrap_or_else(|errors| panic!("{errors}"));
+        }
         app.insert_resource(self.clone());
+        app.init_asset::<WordList>()
+            .init_asset_loader::<WordListLoader>();
         app.add_systems(Startup, setup);
     }
 }
@@ -49,20 +217,153 @@ fn setup(
     asset_resource: Res<AssetManager>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
     let mut assets = AssetStore {
         asset_index: bevy::platform::collections::HashMap::new(),
     };
-    asset_resource
-        .asset_list
-        .iter()
-        .for_each(|(tag, filename, asset_type)| match asset_type {
-            _ => {
-                assets
-                    .asset_index
-                    .insert(tag.clone(), asset_server.load_untyped(filename));
-            }
-        });
+    asset_resource.asset_list.iter().for_each(|asset| {
+        let filename = &asset.filename;
+        let entry = match &asset.asset_type {
+            AssetType::Image => AssetEntry::Image(asset_server.load(filename)),
+            AssetType::Sound => AssetEntry::Sound(asset_server.load(filename)),
+            AssetType::Font => AssetEntry::Font(asset_server.load(filename)),
+            AssetType::SpriteSheet {
+                tile_size,
+                columns,
+                rows,
+            } => AssetEntry::SpriteSheet(SpriteSheet {
+                image: asset_server.load(filename),
+                layout: layouts.add(TextureAtlasLayout::from_grid(
+                    *tile_size, *columns, *rows, None, None,
+                )),
+            }),
+            AssetType::WordList => AssetEntry::WordList(asset_server.load(filename)),
+        };
+        assets.asset_index.insert(asset.tag.clone(), entry);
+    });
     commands.remove_resource::<AssetManager>();
     commands.insert_resource(assets);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{path::Path, process::Command};
+
+    const CHILD: &str = "MY_LIBRARY_ASSET_ROOT_CHILD";
+
+    /// Does nothing in a normal test run; `launch` runs it in a new process
+    /// to report where that process finds its assets.
+    #[test]
+    fn asset_root_child() {
+        let Ok(file_path) = std::env::var(CHILD) else {
+            return;
+        };
+        let assets = AssetManager::new()
+            .file_path(file_path)
+            .add_image("dragon", "dragon.png");
+        println!("asset root: {}", assets.asset_root().display());
+        println!("valid: {}", assets.validate().is_ok());
+    }
+
+    /// Runs [`asset_root_child`] from `dir` with the given environment,
+    /// returning the asset root it found and whether `dragon.png` was there.
+    fn launch(dir: &Path, env: &[(&str, &Path)], file_path: &str) -> (PathBuf, bool) {
+        let mut command = Command::new(std::env::current_exe().unwrap());
+        command
+            .args([
+                "--exact",
+                "bevy_assets::asset_manager::test::asset_root_child",
+                "--nocapture",
+            ])
+            .current_dir(dir)
+            .env_remove("BEVY_ASSET_ROOT")
+            .env_remove("CARGO_MANIFEST_DIR")
+            .env(CHILD, file_path);
+        for (var, value) in env {
+            command.env(var, value);
+        }
+        let output = String::from_utf8(command.output().unwrap().stdout).unwrap();
+        let line = |prefix: &str| {
+            output
+                .lines()
+                .find_map(|line| line.split_once(prefix).map(|(_, rest)| rest))
+                .unwrap_or_else(|| panic!("no {prefix:?} in {output}"))
+                .to_string()
+        };
+        (
+            PathBuf::from(line("asset root: ")),
+            line("valid: ") == "true",
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "no_such_dragon.png (dragon) not found")]
+    fn test_build_validates() {
+        App::new().add_plugins(AssetManager::new().add_image("dragon", "no_such_dragon.png"));
+    }
+
+    #[test]
+    fn test_build_trusts_validate() {
+        let dir = std::env::temp_dir().join("my_library_test_build_trusts_validate");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("dragon.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        let assets = AssetManager::new()
+            .file_path(dir.to_string_lossy())
+            .add_image("dragon", "dragon.png")
+            .validate()
+            .unwrap();
+        // gone since, but not looked for again
+        std::fs::remove_file(dir.join("dragon.png")).unwrap();
+        App::new().add_plugins((MinimalPlugins, AssetPlugin::default(), assets.clone()));
+        assert!(!assets.add_image("wall", "wall.png").validated);
+    }
+
+    #[test]
+    fn test_asset_root() {
+        let temp = std::env::temp_dir().join("my_library_test_asset_root");
+        let game = temp.join("game");
+        let elsewhere = temp.join("elsewhere");
+        for dir in [game.join("assets"), game.join("content"), elsewhere.clone()] {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("dragon.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        }
+        std::fs::remove_file(elsewhere.join("dragon.png")).unwrap();
+
+        // like `cargo run` from a workspace root
+        assert_eq!(
+            launch(&elsewhere, &[("CARGO_MANIFEST_DIR", &game)], "assets"),
+            (game.join("assets"), true)
+        );
+        // the working directory is never used, even if it holds the assets
+        assert_eq!(
+            launch(&game, &[("CARGO_MANIFEST_DIR", &elsewhere)], "assets"),
+            (elsewhere.join("assets"), false)
+        );
+        // BEVY_ASSET_ROOT wins over CARGO_MANIFEST_DIR
+        assert_eq!(
+            launch(
+                &elsewhere,
+                &[
+                    ("BEVY_ASSET_ROOT", &game),
+                    ("CARGO_MANIFEST_DIR", &elsewhere)
+                ],
+                "assets"
+            ),
+            (game.join("assets"), true)
+        );
+        // a custom `file_path`, as given to `AssetPlugin`
+        assert_eq!(
+            launch(&elsewhere, &[("BEVY_ASSET_ROOT", &game)], "content"),
+            (game.join("content"), true)
+        );
+        // a shipped game, started from anywhere, looks next to its executable
+        let exe_dir = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_owned();
+        assert_eq!(launch(&elsewhere, &[], "assets").0, exe_dir.join("assets"));
+    }
+}
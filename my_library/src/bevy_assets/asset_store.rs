@@ -1,28 +1,87 @@
+use crate::WordList;
 use bevy::{
-    asset::{Asset, LoadedUntypedAsset},
+    asset::{Asset, UntypedHandle},
     platform::collections::HashMap,
     prelude::*,
 };
-pub type LoadedAssets = Assets<LoadedUntypedAsset>;
-pub type AssetResource<'w> = Res<'w, LoadedAssets>;
+
+/// The handle of one asset registered with the
+/// [`AssetManager`](crate::AssetManager), typed by its kind.
+#[derive(Clone, Debug)]
+pub enum AssetEntry {
+    Image(Handle<Image>),
+    Sound(Handle<AudioSource>),
+    Font(Handle<Font>),
+    SpriteSheet(SpriteSheet),
+    WordList(Handle<WordList>),
+}
+
+impl AssetEntry {
+    /// The handle of the loaded file (the image, for a sprite sheet).
+    pub fn untyped(&self) -> UntypedHandle {
+        match self {
+            Self::Image(handle) => handle.clone().untyped(),
+            Self::Sound(handle) => handle.clone().untyped(),
+            Self::Font(handle) => handle.clone().untyped(),
+            Self::SpriteSheet(sheet) => sheet.image.clone().untyped(),
+            Self::WordList(handle) => handle.clone().untyped(),
+        }
+    }
+}
+
+/// An image cut into a grid of sprites, with the atlas layout describing
+/// the grid.
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+impl SpriteSheet {
+    /// A sprite showing tile `index`, counting left to right, top to bottom.
+    pub fn sprite(&self, index: usize) -> Sprite {
+        Sprite::from_atlas_image(
+            self.image.clone(),
+            TextureAtlas {
+                layout: self.layout.clone(),
+                index,
+            },
+        )
+    }
+}
 
 #[derive(Resource, Clone)]
 pub struct AssetStore {
-    pub(crate) asset_index: HashMap<String, Handle<LoadedUntypedAsset>>,
+    pub(crate) asset_index: HashMap<String, AssetEntry>,
 }
 
 impl AssetStore {
-    pub fn get_handle<T>(&self, index: &str, assets: &LoadedAssets) -> Option<Handle<T>>
+    /// The entry registered as `index`.
+    pub fn get(&self, index: &str) -> Option<&AssetEntry> {
+        self.asset_index.get(index)
+    }
+
+    /// The handle registered as `index`, or `None` if there is none or it
+    /// holds another type of asset.
+    pub fn get_handle<T>(&self, index: &str) -> Option<Handle<T>>
     where
         T: Asset,
     {
-        if let Some(handle_untyped) = self.asset_index.get(index) {
-            if let Some(handle) = assets.get(handle_untyped) {
-                return Some(handle.handle.clone().typed::<T>());
-            }
-            None
-        } else {
-            None
+        self.get(index)?.untyped().try_typed::<T>().ok()
+    }
+
+    /// Every registered tag with its asset.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AssetEntry)> {
+        self.asset_index
+            .iter()
+            .map(|(tag, entry)| (tag.as_str(), entry))
+    }
+
+    /// The sprite sheet registered as `index`.
+    pub fn sprite_sheet(&self, index: &str) -> Option<&SpriteSheet> {
+        match self.get(index)? {
+            AssetEntry::SpriteSheet(sheet) => Some(sheet),
+            _ => None,
         }
     }
 }
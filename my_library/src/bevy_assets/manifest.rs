@@ -0,0 +1,237 @@
+//! Asset manifests: the assets an [`AssetManager`] loads, listed in a RON
+//! file rather than in code.
+use super::asset_manager::{AssetManager, AssetType};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::UVec2;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct ManifestEntry {
+    tag: String,
+    path: String,
+    #[serde(rename = "type")]
+    asset_type: ManifestType,
+}
+
+#[derive(Deserialize)]
+enum ManifestType {
+    Image,
+    Sound,
+    Font,
+    SpriteSheet {
+        tile_size: (u32, u32),
+        columns: u32,
+        rows: u32,
+    },
+    WordList,
+}
+
+impl From<ManifestType> for AssetType {
+    fn from(asset_type: ManifestType) -> Self {
+        match asset_type {
+            ManifestType::Image => AssetType::Image,
+            ManifestType::Sound => AssetType::Sound,
+            ManifestType::Font => AssetType::Font,
+            ManifestType::SpriteSheet {
+                tile_size: (width, height),
+                columns,
+                rows,
+            } => AssetType::SpriteSheet {
+                tile_size: UVec2::new(width, height),
+                columns,
+                rows,
+            },
+            ManifestType::WordList => AssetType::WordList,
+        }
+    }
+}
+
+/// The line (counting from 1) of the first `path: "path"` field at or after
+/// byte `from`, and the byte just past it. Only a `path` key counts, so a
+/// tag or other field with the same text isn't mistaken for it.
+fn find_path(text: &str, from: usize, path: &str) -> Option<(usize, usize)> {
+    let quoted = format!("\"{path}\"");
+    text[from..].match_indices("path").find_map(|(found, _)| {
+        let start = from + found;
+        // a whole identifier, not the end of a longer one or of a string
+        let before = text[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '"') {
+            return None;
+        }
+        let value = text[start + "path".len()..]
+            .trim_start()
+            .strip_prefix(':')?
+            .trim_start();
+        value.starts_with(&quoted).then(|| {
+            let end = text.len() - value.len() + quoted.len();
+            (text[..start].matches('\n').count() + 1, end)
+        })
+    })
+}
+
+impl AssetManager {
+    /// Reads the assets to load from a RON manifest, such as
+    /// `assets/manifest.ron`, so assets can be added without recompiling.
+    /// The manifest is a list of entries with a tag, a path inside the
+    /// assets directory and a type:
+    ///
+    /// ```ron
+    /// [
+    ///     (tag: "dragon", path: "flappy_dragon.png", type: Image),
+    ///     (tag: "flap", path: "flap.ogg", type: Sound),
+    ///     (tag: "dice", path: "dice.png",
+    ///         type: SpriteSheet(tile_size: (52, 52), columns: 6, rows: 1)),
+    /// ]
+    /// ```
+    ///
+    /// The types are `Image`, `Sound`, `Font`, `SpriteSheet` and `WordList`,
+    /// matching the `add_*` methods. The assets are checked with
+    /// [`AssetManager::validate`], and each problem reported names its
+    /// manifest line; the error is an [`AssetErrors`](crate::AssetErrors)
+    /// unless the manifest itself could not be read.
+    ///
+    /// A relative `manifest` path is found from the same base directory as
+    /// the assets (see [`asset_root`](AssetManager::asset_root)), so
+    /// `assets/manifest.ron` works whatever the working directory.
+    ///
+    /// To list formats beyond those Bevy loads by default, allow their
+    /// extensions first and use [`add_manifest`](AssetManager::add_manifest):
+    ///
+    /// ```no_run
+    /// # use my_library::{AssetManager, AssetType};
+    /// let assets = AssetManager::new()
+    ///     .allow_extension(AssetType::Image, "jpg")
+    ///     .add_manifest("assets/manifest.ron")?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn from_manifest<P: AsRef<Path>>(manifest: P) -> anyhow::Result<Self> {
+        Self::new().add_manifest(manifest)
+    }
+
+    /// Adds the assets listed in a manifest, as read by
+    /// [`from_manifest`](AssetManager::from_manifest), to those already
+    /// registered. Use this after [`file_path`](AssetManager::file_path) if
+    /// the assets aren't in `assets`.
+    pub fn add_manifest<P: AsRef<Path>>(self, manifest: P) -> anyhow::Result<Self> {
+        let manifest = manifest.as_ref();
+        let name = manifest.display();
+        #[cfg(not(target_arch = "wasm32"))]
+        let file = FileAssetReader::get_base_path().join(manifest);
+        #[cfg(target_arch = "wasm32")]
+        let file = manifest.to_path_buf();
+        let text = std::fs::read_to_string(&file)
+            .map_err(|err| anyhow::Error::msg(format!("{}: {err}", file.display())))?;
+        let entries: Vec<ManifestEntry> =
+            ron::from_str(&text).map_err(|err| anyhow::Error::msg(format!("{name}:{err}")))?;
+        let mut assets = self;
+        let mut offset = 0;
+        for entry in entries {
+            let origin = match find_path(&text, offset, &entry.path) {
+                Some((line, end)) => {
+                    offset = end;
+                    format!("{name}:{line}")
+                }
+                None => name.to_string(),
+            };
+            assets = assets.add(entry.tag, entry.path, entry.asset_type.into(), Some(origin));
+        }
+        Ok(assets.validate()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn manifest(name: &str, text: &str) -> anyhow::Result<AssetManager> {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, text).unwrap();
+        AssetManager::from_manifest(path)
+    }
+
+    #[test]
+    fn test_find_path() {
+        let text = "[\n  (path: \"a.png\"),\n  (path: \"a.png\"),\n]";
+        let (line, end) = find_path(text, 0, "a.png").unwrap();
+        assert_eq!(line, 2);
+        assert_eq!(find_path(text, end, "a.png").unwrap().0, 3);
+        assert!(find_path(text, 0, "b.png").is_none());
+
+        // tags and other keys with the same text are skipped
+        let text = "[\n  (tag: \"path\",\n   filepath: \"a.png\",\n   path: \"a.png\"),\n]";
+        assert_eq!(find_path(text, 0, "a.png").unwrap().0, 4);
+        let text = "[\n  (tag: \"a.png\",\n   path:\"a.png\", type: Image),\n]";
+        let (line, end) = find_path(text, 0, "a.png").unwrap();
+        assert_eq!(line, 3);
+        assert_eq!(&text[end..end + 1], ",");
+    }
+
+    #[test]
+    fn test_tag_matches_path() {
+        let err = manifest(
+            "my_library_tag_path_manifest.ron",
+            "[\n    (tag: \"gone.png\",\n        path: \"gone.png\", type: Image),\n]",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("my_library_tag_path_manifest.ron:3: "),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_empty_manifest() {
+        let assets = manifest("my_library_empty_manifest.ron", "[]").unwrap();
+        assert!(assets.asset_list.is_empty());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let err = manifest(
+            "my_library_missing_manifest.ron",
+            "[\n    (tag: \"dice\", path: \"no_such_dice.png\",\n        type: SpriteSheet(tile_size: (52, 52), columns: 6, rows: 1)),\n]",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("my_library_missing_manifest.ron:2: "), "{err}");
+        assert!(err.contains("no_such_dice.png (dice) not found"), "{err}");
+    }
+
+    #[test]
+    fn test_allowed_extension() {
+        let dir = std::env::temp_dir().join("my_library_test_manifest_extension");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("wall.JPG"), b"\xff\xd8\xff").unwrap();
+        let path = dir.join("manifest.ron");
+        std::fs::write(&path, "[(tag: \"wall\", path: \"wall.JPG\", type: Image)]").unwrap();
+        let assets = AssetManager::new().file_path(dir.to_string_lossy());
+        let err = assets.clone().add_manifest(&path).unwrap_err().to_string();
+        assert!(err.contains("unsupported extension"), "{err}");
+        let assets = assets
+            .allow_extension(AssetType::Image, "jpg")
+            .add_manifest(&path)
+            .unwrap();
+        assert_eq!(assets.asset_list.len(), 1);
+    }
+
+    #[test]
+    fn test_bad_manifest() {
+        let err = manifest(
+            "my_library_bad_manifest.ron",
+            "[\n    (tag: \"dragon\", path: \"dragon.png\", type: Picture),\n]",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("my_library_bad_manifest.ron:2:"), "{err}");
+        assert!(err.contains("Picture"), "{err}");
+        assert!(
+            AssetManager::from_manifest("no_such_manifest.ron")
+                .unwrap_err()
+                .to_string()
+                .contains("no_such_manifest.ron: ")
+        );
+    }
+}
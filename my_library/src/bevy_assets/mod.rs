@@ -1,5 +1,10 @@
 mod asset_manager;
-pub use asset_manager::AssetManager;
+pub use asset_manager::{AssetManager, AssetType};
+
+mod manifest;
+
+mod validation;
+pub use validation::*;
 
 mod asset_store;
 pub use asset_store::*;
@@ -7,11 +12,10 @@ pub use asset_store::*;
 #[macro_export]
 macro_rules! spawn_image {
 ($assets:expr, $commands:expr, $index:expr, $x:expr, $y:expr, $z:expr,
-$resource:expr,
 $($component:expr),*) =>
 {
 $commands.spawn((
-Sprite::from_image($assets.get_handle($index, $resource).unwrap()),
+Sprite::from_image($assets.get_handle($index).unwrap()),
 Transform::from_xyz($x, $y, $z),
 ))
 $(
@@ -0,0 +1,471 @@
+//! Checks the files registered with an [`AssetManager`] before Bevy tries
+//! to load them, reporting every problem at once.
+use super::asset_manager::{AssetManager, AssetType};
+use bevy::platform::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// A problem with one of the assets registered with an [`AssetManager`],
+/// found by [`AssetManager::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssetError {
+    /// There is no file at `path` in the assets directory.
+    MissingFile { tag: String, path: String },
+    /// `tag` was registered more than once; `path` is the later file.
+    DuplicateTag { tag: String, path: String },
+    /// Bevy can't load files with this extension as `asset_type`; the
+    /// extensions it can are `expected`.
+    UnsupportedExtension {
+        tag: String,
+        path: String,
+        asset_type: AssetType,
+        expected: Vec<String>,
+    },
+    /// The file doesn't start with a valid header for its image format.
+    UndecodableImage { tag: String, path: String },
+    /// The file only exists as `found`, which differs in case. This works on
+    /// case-insensitive filesystems (Windows, macOS) but not elsewhere.
+    CaseMismatch {
+        tag: String,
+        path: String,
+        found: String,
+    },
+    /// The file exists but could not be read.
+    Io {
+        tag: String,
+        path: String,
+        message: String,
+    },
+}
+
+impl AssetError {
+    /// The tag of the asset with the problem.
+    pub fn tag(&self) -> &str {
+        match self {
+            AssetError::MissingFile { tag, .. }
+            | AssetError::DuplicateTag { tag, .. }
+            | AssetError::UnsupportedExtension { tag, .. }
+            | AssetError::UndecodableImage { tag, .. }
+            | AssetError::CaseMismatch { tag, .. }
+            | AssetError::Io { tag, .. } => tag,
+        }
+    }
+}
+
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetError::MissingFile { tag, path } => {
+                write!(f, "{path} ({tag}) not found in assets directory")
+            }
+            AssetError::DuplicateTag { tag, path } => {
+                write!(f, "{path} ({tag}): tag {tag:?} is already in use")
+            }
+            AssetError::UnsupportedExtension {
+                tag,
+                path,
+                expected,
+                ..
+            } => write!(
+                f,
+                "{path} ({tag}): unsupported extension, expected one of {}",
+                expected.join(", ")
+            ),
+            AssetError::UndecodableImage { tag, path } => {
+                write!(f, "{path} ({tag}) is not a valid image file")
+            }
+            AssetError::CaseMismatch { tag, path, found } => write!(
+                f,
+                "{path} ({tag}) is named {found}, which only matches on case-insensitive filesystems"
+            ),
+            AssetError::Io { tag, path, message } => {
+                write!(f, "{path} ({tag}) could not be read: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+/// Every problem [`AssetManager::validate`] found, in registration order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetErrors {
+    // each error with where its asset was registered, if known
+    errors: Vec<(Option<String>, AssetError)>,
+}
+
+impl AssetErrors {
+    /// The problems found.
+    pub fn iter(&self) -> impl Iterator<Item = &AssetError> {
+        self.errors.iter().map(|(_, error)| error)
+    }
+}
+
+impl std::fmt::Display for AssetErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self.errors.len();
+        write!(
+            f,
+            "{count} asset problem{}:",
+            if count == 1 { "" } else { "s" }
+        )?;
+        for (origin, error) in &self.errors {
+            match origin {
+                Some(origin) => write!(f, "\n{origin}: {error}")?,
+                None => write!(f, "\n{error}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AssetErrors {}
+
+/// How a relative path was found under a directory.
+enum Lookup {
+    Exact,
+    /// Found, with this spelling, only by ignoring case.
+    Differs(String),
+    Missing,
+}
+
+/// Looks `path` up one component at a time, so differences in case are
+/// caught whatever the filesystem.
+fn lookup(root: &Path, path: &str) -> Lookup {
+    let mut dir = root.to_path_buf();
+    let mut found = Vec::new();
+    let mut differs = false;
+    for component in Path::new(path).components() {
+        let Component::Normal(name) = component else {
+            dir.push(component);
+            found.push(component.as_os_str().to_string_lossy().into_owned());
+            continue;
+        };
+        let name = name.to_string_lossy();
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return Lookup::Missing;
+        };
+        let names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        let actual = if names.iter().any(|entry| *entry == name) {
+            name.into_owned()
+        } else if let Some(entry) = names
+            .into_iter()
+            .find(|entry| entry.to_lowercase() == name.to_lowercase())
+        {
+            differs = true;
+            entry
+        } else {
+            return Lookup::Missing;
+        };
+        dir.push(&actual);
+        found.push(actual);
+    }
+    if differs {
+        Lookup::Differs(found.join("/"))
+    } else {
+        Lookup::Exact
+    }
+}
+
+/// Whether `header` starts like a file with image `extension`, given in
+/// lower case.
+fn valid_header(extension: &str, header: &[u8]) -> bool {
+    match extension {
+        "png" => header.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "hdr" => header.starts_with(b"#?"),
+        // the first 8 of KTX 2's 12-byte identifier
+        "ktx2" => header.starts_with(b"\xabKTX 20\xbb"),
+        _ => true,
+    }
+}
+
+fn read_header(path: &Path) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    let mut header = Vec::with_capacity(8);
+    std::fs::File::open(path)?
+        .take(8)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+impl AssetManager {
+    /// Checks every registered asset and reports all the problems found:
+    /// missing files, duplicate tags, extensions Bevy can't load as the
+    /// asset's type, images with broken headers and filenames that only
+    /// match by ignoring case. Extensions are compared ignoring case, and
+    /// more can be accepted with
+    /// [`allow_extension`](AssetManager::allow_extension). Files are looked
+    /// for in [`asset_root`](AssetManager::asset_root).
+    ///
+    /// Adding a manager that passed as a plugin doesn't check it again,
+    /// unless assets or settings were changed since.
+    pub fn validate(mut self) -> Result<Self, AssetErrors> {
+        self.check()?;
+        self.validated = true;
+        Ok(self)
+    }
+
+    /// [`validate`](AssetManager::validate), without taking the manager.
+    pub(super) fn check(&self) -> Result<(), AssetErrors> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let root = Some(self.asset_root());
+        #[cfg(target_arch = "wasm32")]
+        let root = None;
+        let errors = self.problems(root.as_deref());
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AssetErrors { errors })
+        }
+    }
+
+    /// The problems with the registered assets, checking files under `root`
+    /// if there is a filesystem to check.
+    fn problems(&self, root: Option<&Path>) -> Vec<(Option<String>, AssetError)> {
+        let mut errors = Vec::new();
+        let mut tags = HashSet::new();
+        for asset in &self.asset_list {
+            let tag = asset.tag.clone();
+            let path = asset.filename.clone();
+            let mut report = |error| errors.push((asset.origin.clone(), error));
+            if !tags.insert(&asset.tag) {
+                report(AssetError::DuplicateTag {
+                    tag: tag.clone(),
+                    path: path.clone(),
+                });
+            }
+            let extension = Path::new(&path)
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            let expected = self.extensions(&asset.asset_type);
+            let supported = expected.contains(&extension);
+            if !supported {
+                report(AssetError::UnsupportedExtension {
+                    tag: tag.clone(),
+                    path: path.clone(),
+                    asset_type: asset.asset_type.clone(),
+                    expected,
+                });
+            }
+            let Some(root) = root else {
+                continue;
+            };
+            let file: PathBuf = match lookup(root, &path) {
+                Lookup::Exact => root.join(&path),
+                Lookup::Differs(found) => {
+                    let file = root.join(&found);
+                    report(AssetError::CaseMismatch {
+                        tag: tag.clone(),
+                        path: path.clone(),
+                        found,
+                    });
+                    file
+                }
+                Lookup::Missing => {
+                    report(AssetError::MissingFile { tag, path });
+                    continue;
+                }
+            };
+            if asset.asset_type.is_image() && supported {
+                match read_header(&file) {
+                    Ok(header) if valid_header(&extension, &header) => {}
+                    Ok(_) => report(AssetError::UndecodableImage { tag, path }),
+                    Err(err) => report(AssetError::Io {
+                        tag,
+                        path,
+                        message: err.to_string(),
+                    }),
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::math::UVec2;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const KTX2: &[u8] = b"\xabKTX 20\xbb\r\n\x1a\n";
+
+    fn assets_dir(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&root);
+        for (file, contents) in files {
+            let file = root.join(file);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, contents).unwrap();
+        }
+        root
+    }
+
+    fn problems(assets: &AssetManager, root: &Path) -> Vec<AssetError> {
+        assets
+            .problems(Some(root))
+            .into_iter()
+            .map(|(_, error)| error)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_assets() {
+        let root = assets_dir(
+            "my_library_test_valid_assets",
+            &[
+                ("dragon.png", PNG),
+                ("sprites/dice.png", PNG),
+                ("sky.ktx2", KTX2),
+                ("flap.ogg", b"OggS"),
+                ("names.names", b"anna\n"),
+            ],
+        );
+        let assets = AssetManager::new()
+            .add_image("dragon", "dragon.png")
+            .add_sprite_sheet("dice", "sprites/dice.png", UVec2::splat(52), 6, 1)
+            .add_image("sky", "sky.ktx2")
+            .add_sound("flap", "flap.ogg")
+            .add_word_list("names", "names.names");
+        assert_eq!(problems(&assets, &root), vec![]);
+    }
+
+    #[test]
+    fn test_every_problem_reported() {
+        let root = assets_dir(
+            "my_library_test_asset_problems",
+            &[
+                ("dragon.png", PNG),
+                ("Wall.png", PNG),
+                ("broken.png", b"GIF89a"),
+                ("broken.ktx2", PNG),
+                ("font.ttf", b""),
+            ],
+        );
+        let assets = AssetManager::new()
+            .add_image("dragon", "dragon.png")
+            .add_image("dragon", "dragon.png")
+            .add_image("wall", "wall.png")
+            .add_image("broken", "broken.png")
+            .add_image("broken_ktx2", "broken.ktx2")
+            .add_image("missing", "missing.png")
+            .add_sound("font", "font.ttf");
+        assert_eq!(
+            problems(&assets, &root),
+            vec![
+                AssetError::DuplicateTag {
+                    tag: "dragon".to_string(),
+                    path: "dragon.png".to_string(),
+                },
+                AssetError::CaseMismatch {
+                    tag: "wall".to_string(),
+                    path: "wall.png".to_string(),
+                    found: "Wall.png".to_string(),
+                },
+                AssetError::UndecodableImage {
+                    tag: "broken".to_string(),
+                    path: "broken.png".to_string(),
+                },
+                AssetError::UndecodableImage {
+                    tag: "broken_ktx2".to_string(),
+                    path: "broken.ktx2".to_string(),
+                },
+                AssetError::MissingFile {
+                    tag: "missing".to_string(),
+                    path: "missing.png".to_string(),
+                },
+                AssetError::UnsupportedExtension {
+                    tag: "font".to_string(),
+                    path: "font.ttf".to_string(),
+                    asset_type: AssetType::Sound,
+                    expected: vec!["ogg".to_string(), "oga".to_string(), "spx".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extensions() {
+        let root = assets_dir(
+            "my_library_test_asset_extensions",
+            &[
+                ("DRAGON.PNG", PNG),
+                ("broken.Png", b"GIF89a"),
+                ("wall.jpg", b"\xff\xd8\xff"),
+                ("dice.JPEG", b"\xff\xd8\xff"),
+                ("flap.wav", b"RIFF"),
+            ],
+        );
+        let assets = AssetManager::new()
+            .add_image("dragon", "DRAGON.PNG")
+            .add_image("broken", "broken.Png")
+            .add_image("wall", "wall.jpg")
+            .add_sprite_sheet("dice", "dice.JPEG", UVec2::splat(52), 6, 1)
+            .add_sound("flap", "flap.wav");
+        // upper case PNGs are checked like any other, so "broken" is caught
+        let errors = problems(&assets, &root);
+        assert!(matches!(errors[0], AssetError::UndecodableImage { .. }));
+        let unsupported: Vec<&str> = errors[1..].iter().map(AssetError::tag).collect();
+        assert_eq!(unsupported, ["wall", "dice", "flap"]);
+
+        // allowed for images, so for sprite sheets too, but not for sounds
+        let assets = assets
+            .allow_extension(AssetType::Image, "JPG")
+            .allow_extension(AssetType::Image, "jpeg");
+        assert_eq!(
+            problems(&assets, &root),
+            vec![
+                AssetError::UndecodableImage {
+                    tag: "broken".to_string(),
+                    path: "broken.Png".to_string(),
+                },
+                AssetError::UnsupportedExtension {
+                    tag: "flap".to_string(),
+                    path: "flap.wav".to_string(),
+                    asset_type: AssetType::Sound,
+                    expected: vec!["ogg".to_string(), "oga".to_string(), "spx".to_string()],
+                },
+            ]
+        );
+        let assets = assets.allow_extension(AssetType::Sound, "wav");
+        assert_eq!(problems(&assets, &root).len(), 1);
+    }
+
+    #[test]
+    fn test_case_mismatch_in_directory() {
+        let root = assets_dir("my_library_test_asset_case", &[("Sprites/dice.png", PNG)]);
+        let assets = AssetManager::new().add_image("dice", "sprites/dice.png");
+        assert_eq!(
+            problems(&assets, &root),
+            vec![AssetError::CaseMismatch {
+                tag: "dice".to_string(),
+                path: "sprites/dice.png".to_string(),
+                found: "Sprites/dice.png".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let assets = AssetManager::new().add_image("a", "a.gif").add(
+            "b".to_string(),
+            "b.gif".to_string(),
+            AssetType::Image,
+            Some("manifest.ron:3".to_string()),
+        );
+        let errors = AssetErrors {
+            errors: assets.problems(None),
+        };
+        assert_eq!(errors.iter().count(), 2);
+        assert_eq!(errors.iter().nth(1).unwrap().tag(), "b");
+        assert_eq!(
+            errors.to_string(),
+            "2 asset problems:\n\
+             a.gif (a): unsupported extension, expected one of png, hdr, ktx2\n\
+             manifest.ron:3: b.gif (b): unsupported extension, expected one of png, hdr, ktx2"
+        );
+    }
+}
@@ -27,7 +27,7 @@ pub(crate) fn setup<T>(
             panic!("Unknown menu state.")
         }
     };
-    commands.spawn((Camera2d::default(), MenuElement));
+    commands.spawn((Camera2d, MenuElement));
     commands.spawn((
         Sprite {
             image: menu_graphic,
@@ -0,0 +1,313 @@
+//! The loading phase: holds the game back until every asset in the
+//! [`AssetStore`] (and the menu images) has loaded.
+use super::{MenuAssets, MenuResource};
+use crate::AssetStore;
+use bevy::{
+    app::AppExit,
+    asset::{RecursiveDependencyLoadState, UntypedAssetId},
+    prelude::*,
+    state::state::FreelyMutableState,
+};
+
+/// How much of the game's assets have finished loading, from 0 to 1.
+/// Updated every frame of the loading phase set up by
+/// [`GameStatePlugin::with_loading`](crate::GameStatePlugin::with_loading).
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoadingProgress(pub f32);
+
+/// An asset that could not be loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetLoadFailure {
+    /// The tag the asset was registered under.
+    pub tag: String,
+    /// The file it was loaded from.
+    pub path: String,
+    /// Why loading failed.
+    pub error: String,
+}
+
+/// Every asset that failed to load. Inserted as a resource before the game
+/// moves to its failure state.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssetLoadFailures(pub Vec<AssetLoadFailure>);
+
+#[derive(Resource)]
+pub(crate) struct LoadingStates<T> {
+    pub(crate) failed: T,
+}
+
+#[derive(Component)]
+pub(crate) struct LoadingElement;
+
+/// Where loading stands.
+#[derive(Debug, PartialEq)]
+enum Status {
+    /// Still loading, with the share of assets already done.
+    Loading(f32),
+    Loaded,
+    Failed(Vec<AssetLoadFailure>),
+}
+
+/// Sums up the load states of `(tag, path, state)` triples. Assets the
+/// server doesn't track (added directly to `Assets`) count as loaded.
+fn status(assets: Vec<(String, String, Option<RecursiveDependencyLoadState>)>) -> Status {
+    let total = assets.len();
+    let mut done = 0;
+    let mut failures = Vec::new();
+    for (tag, path, state) in assets {
+        match state {
+            None | Some(RecursiveDependencyLoadState::Loaded) => done += 1,
+            Some(RecursiveDependencyLoadState::Failed(error)) => {
+                done += 1;
+                failures.push(AssetLoadFailure {
+                    tag,
+                    path,
+                    error: error.to_string(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    if done < total {
+        Status::Loading(done as f32 / total as f32)
+    } else if failures.is_empty() {
+        Status::Loaded
+    } else {
+        Status::Failed(failures)
+    }
+}
+
+pub(crate) fn setup(mut commands: Commands) {
+    commands.insert_resource(LoadingProgress(0.0));
+    commands.spawn((Camera2d, LoadingElement));
+    commands.spawn((Text::new("Loading..."), LoadingElement));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run<T>(
+    asset_server: Res<AssetServer>,
+    store: Option<Res<AssetStore>>,
+    menu_assets: Option<Res<MenuAssets>>,
+    menu: Res<MenuResource<T>>,
+    states: Res<LoadingStates<T>>,
+    mut progress: ResMut<LoadingProgress>,
+    mut text: Query<&mut Text, With<LoadingElement>>,
+    mut state: ResMut<NextState<T>>,
+    mut commands: Commands,
+) where
+    T: States + FreelyMutableState + Copy,
+{
+    let mut assets: Vec<(String, UntypedAssetId)> = Vec::new();
+    if let Some(store) = &store {
+        assets.extend(
+            store
+                .iter()
+                .map(|(tag, entry)| (tag.to_string(), entry.untyped().id())),
+        );
+    }
+    if let Some(menu_assets) = &menu_assets {
+        assets.push((
+            "main_menu".to_string(),
+            menu_assets.main_menu.id().untyped(),
+        ));
+        assets.push((
+            "game_over".to_string(),
+            menu_assets.game_over.id().untyped(),
+        ));
+    }
+    let assets = assets
+        .into_iter()
+        .map(|(tag, id)| {
+            let path = asset_server
+                .get_path(id)
+                .map(|path| path.to_string())
+                .unwrap_or_default();
+            (
+                tag,
+                path,
+                asset_server.get_recursive_dependency_load_state(id),
+            )
+        })
+        .collect();
+
+    match status(assets) {
+        Status::Loading(done) => {
+            progress.0 = done;
+            for mut text in &mut text {
+                text.0 = format!("Loading... {:.0}%", done * 100.0);
+            }
+        }
+        Status::Loaded => {
+            progress.0 = 1.0;
+            state.set(menu.menu_state);
+        }
+        Status::Failed(failures) => {
+            progress.0 = 1.0;
+            for failure in &failures {
+                error!(
+                    "Could not load asset {:?} from {:?}: {}",
+                    failure.tag, failure.path, failure.error
+                );
+            }
+            commands.insert_resource(AssetLoadFailures(failures));
+            state.set(states.failed);
+        }
+    }
+}
+
+pub(crate) fn setup_failed(mut commands: Commands, failures: Option<Res<AssetLoadFailures>>) {
+    let mut message = String::from("Some game files could not be loaded:\n\n");
+    for failure in failures.iter().flat_map(|failures| &failures.0) {
+        message += &format!("{} ({}): {}\n", failure.tag, failure.path, failure.error);
+    }
+    message += "\nPress Q to quit.";
+    commands.spawn((Camera2d, LoadingElement));
+    commands.spawn((Text::new(message), LoadingElement));
+}
+
+pub(crate) fn run_failed(keyboard: Res<ButtonInput<KeyCode>>, mut exit: EventWriter<AppExit>) {
+    if keyboard.just_pressed(KeyCode::KeyQ) {
+        exit.write(AppExit::error());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::GameStatePlugin;
+    use bevy::{
+        asset::{
+            AssetLoadError, AssetLoader, LoadContext,
+            io::{AssetReaderError, Reader},
+        },
+        state::app::StatesPlugin,
+    };
+    use std::sync::Arc;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default, States)]
+    enum Phase {
+        #[default]
+        Menu,
+        Loading,
+        Playing,
+        GameOver,
+        LoadFailed,
+    }
+
+    // decodes nothing: any file named `.png` becomes a blank image
+    #[derive(Default)]
+    struct BlankImageLoader;
+
+    impl AssetLoader for BlankImageLoader {
+        type Asset = Image;
+        type Settings = ();
+        type Error = std::io::Error;
+
+        async fn load(
+            &self,
+            _reader: &mut dyn Reader,
+            _settings: &(),
+            _load_context: &mut LoadContext<'_>,
+        ) -> Result<Image, std::io::Error> {
+            Ok(Image::default())
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["png"]
+        }
+    }
+
+    /// Runs a game whose assets live in `dir` until it leaves the loading
+    /// phase.
+    fn load(dir: &str, files: &[&str]) -> App {
+        let root = std::env::temp_dir().join(dir);
+        std::fs::create_dir_all(&root).unwrap();
+        for file in files {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: root.to_string_lossy().into_owned(),
+                ..default()
+            },
+            StatesPlugin,
+        ))
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_asset::<Image>()
+        .init_asset_loader::<BlankImageLoader>()
+        .add_plugins(
+            GameStatePlugin::new(Phase::Menu, Phase::Playing, Phase::GameOver)
+                .with_loading(Phase::Loading, Phase::LoadFailed),
+        );
+        for _ in 0..500 {
+            app.update();
+            if *app.world().resource::<State<Phase>>().get() != Phase::Loading {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        app
+    }
+
+    #[test]
+    fn test_status() {
+        let asset = |state| ("tag".to_string(), "file.png".to_string(), state);
+        assert_eq!(status(vec![]), Status::Loaded);
+        assert_eq!(
+            status(vec![
+                asset(Some(RecursiveDependencyLoadState::Loaded)),
+                asset(None),
+                asset(Some(RecursiveDependencyLoadState::Loading)),
+                asset(Some(RecursiveDependencyLoadState::NotLoaded)),
+            ]),
+            Status::Loading(0.5)
+        );
+        let error = Arc::new(AssetLoadError::AssetReaderError(
+            AssetReaderError::NotFound("file.png".into()),
+        ));
+        // a failure waits for the rest, so every failure is reported at once
+        let failed = asset(Some(RecursiveDependencyLoadState::Failed(error)));
+        assert!(matches!(
+            status(vec![
+                failed.clone(),
+                asset(Some(RecursiveDependencyLoadState::Loading))
+            ]),
+            Status::Loading(_)
+        ));
+        let Status::Failed(failures) = status(vec![
+            failed.clone(),
+            asset(Some(RecursiveDependencyLoadState::Loaded)),
+            failed,
+        ]) else {
+            panic!("expected a failure");
+        };
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].path, "file.png");
+    }
+
+    #[test]
+    fn test_loaded() {
+        let app = load(
+            "my_library_test_loading",
+            &["main_menu.png", "game_over.png"],
+        );
+        assert_eq!(app.world().resource::<State<Phase>>().get(), &Phase::Menu);
+        assert_eq!(app.world().resource::<LoadingProgress>().0, 1.0);
+        assert!(app.world().get_resource::<AssetLoadFailures>().is_none());
+    }
+
+    #[test]
+    fn test_failed() {
+        let app = load("my_library_test_loading_failed", &["main_menu.png"]);
+        assert_eq!(
+            app.world().resource::<State<Phase>>().get(),
+            &Phase::LoadFailed
+        );
+        let failures = &app.world().resource::<AssetLoadFailures>().0;
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].tag, "game_over");
+        assert_eq!(failures[0].path, "game_over.png");
+    }
+}
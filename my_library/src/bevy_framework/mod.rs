@@ -2,6 +2,8 @@ use bevy::prelude::*;
 use bevy::{ecs::world::FromWorld, state::state::FreelyMutableState, state::state::States};
 
 mod game_menus;
+mod loading;
+pub use loading::{AssetLoadFailure, AssetLoadFailures, LoadingProgress};
 
 #[macro_export]
 macro_rules! add_phase {
@@ -49,6 +51,7 @@ pub struct GameStatePlugin<T> {
     menu_state: T,
     game_start_state: T,
     game_end_state: T,
+    loading: Option<(T, T)>,
 }
 
 impl<T> GameStatePlugin<T> {
@@ -58,8 +61,20 @@ impl<T> GameStatePlugin<T> {
             menu_state,
             game_start_state,
             game_end_state,
+            loading: None,
         }
     }
+
+    /// Starts the game in `loading_state`, which waits for every asset in
+    /// the [`AssetStore`](crate::AssetStore) and the menu images to load,
+    /// updating [`LoadingProgress`] as it goes. Once all of them have
+    /// loaded the game moves on to the menu; if any fail it moves to
+    /// `failed_state` instead, which lists the [`AssetLoadFailures`] and
+    /// lets the player quit.
+    pub fn with_loading(mut self, loading_state: T, failed_state: T) -> Self {
+        self.loading = Some((loading_state, failed_state));
+        self
+    }
 }
 
 impl<T> Plugin for GameStatePlugin<T>
@@ -67,7 +82,20 @@ where
     T: States + Copy + FromWorld + FreelyMutableState,
 {
     fn build(&self, app: &mut App) {
-        app.init_state::<T>();
+        if let Some((loading_state, failed_state)) = self.loading {
+            app.insert_state(loading_state);
+            app.insert_resource(loading::LoadingStates {
+                failed: failed_state,
+            });
+            app.add_systems(OnEnter(loading_state), loading::setup);
+            app.add_systems(Update, loading::run::<T>.run_if(in_state(loading_state)));
+            app.add_systems(OnExit(loading_state), cleanup::<loading::LoadingElement>);
+            app.add_systems(OnEnter(failed_state), loading::setup_failed);
+            app.add_systems(Update, loading::run_failed.run_if(in_state(failed_state)));
+            app.add_systems(OnExit(failed_state), cleanup::<loading::LoadingElement>);
+        } else {
+            app.init_state::<T>();
+        }
         app.add_systems(Startup, setup_menus);
         let start = MenuResource {
             menu_state: self.menu_state,
@@ -0,0 +1,313 @@
+//! Roll a dice expression many times and compare what came up with the
+//! exact odds.
+//!
+//! ```text
+//! cargo run --release --bin dice_stats -- 4d6kh3 --trials 100000 --seed 42 --csv stats.csv
+//! ```
+use my_library::{DiceExpr, DiceStats, RandomNumberGenerator};
+use std::{collections::BTreeMap, fmt::Write as _, path::PathBuf, process::exit};
+
+const USAGE: &str =
+    "usage: dice_stats <dice> [--trials N] [--seed N] [--width N] [--rows N] [--csv FILE]
+
+  <dice>        dice notation, such as 3d6, 4d6kh3, 2d20kl1+5 or 1d6!
+  --trials N    number of rolls (default 100000)
+  --seed N      random seed (default: random, printed so runs can be repeated)
+  --width N     width of the longest histogram bar (default 50)
+  --rows N      most histogram rows; wide ranges share a row (default 40)
+  --csv FILE    also write total,count,frequency,exact to FILE";
+
+const PERCENTILES: [f64; 7] = [1.0, 5.0, 25.0, 50.0, 75.0, 95.0, 99.0];
+
+struct Options {
+    dice: DiceExpr,
+    trials: u64,
+    seed: Option<u64>,
+    width: usize,
+    rows: u64,
+    csv: Option<PathBuf>,
+}
+
+fn parse_options(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut args = args.into_iter();
+    let mut dice = None;
+    let (mut trials, mut seed, mut width, mut rows, mut csv) = (100_000, None, 50, 40, None);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        let number = |value: String| {
+            value
+                .parse()
+                .map_err(|_| format!("{arg} takes a number, not {value:?}"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                exit(0);
+            }
+            "--trials" => trials = number(value()?)?,
+            "--seed" => seed = Some(number(value()?)?),
+            "--width" => width = number(value()?)? as usize,
+            "--rows" => rows = number(value()?)?,
+            "--csv" => csv = Some(value()?.into()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if dice.is_some() => return Err(format!("unexpected argument {arg:?}")),
+            _ => dice = Some(arg.parse().map_err(|err| format!("{arg:?}: {err}"))?),
+        }
+    }
+    if trials == 0 {
+        return Err("--trials must be at least 1".into());
+    }
+    if rows == 0 {
+        return Err("--rows must be at least 1".into());
+    }
+    Ok(Options {
+        dice: dice.ok_or("no dice given")?,
+        trials,
+        seed,
+        width,
+        rows,
+        csv,
+    })
+}
+
+fn exact_mean_and_variance(exact: &BTreeMap<i64, f64>) -> (f64, f64) {
+    let mean: f64 = exact.iter().map(|(t, p)| *t as f64 * p).sum();
+    let variance = exact
+        .iter()
+        .map(|(t, p)| (*t as f64 - mean).powi(2) * p)
+        .sum();
+    (mean, variance)
+}
+
+fn exact_percentile(exact: &BTreeMap<i64, f64>, percent: f64) -> Option<i64> {
+    let mut seen = 0.0;
+    exact.iter().find_map(|(total, chance)| {
+        seen += chance;
+        // tolerate rounding in the running sum
+        (seen >= percent / 100.0 - 1e-9).then_some(*total)
+    })
+}
+
+fn report(options: &Options, stats: &DiceStats, exact: Option<&BTreeMap<i64, f64>>) -> String {
+    let mut out = String::new();
+    let (mean, variance) = (stats.mean(), stats.variance());
+    let _ = write!(out, "mean {mean:.3}, variance {variance:.3}");
+    let _ = write!(out, ", std dev {:.3}", variance.sqrt());
+    if let Some(exact) = exact {
+        let (mean, variance) = exact_mean_and_variance(exact);
+        let _ = write!(out, "\nexact mean {mean:.3}, variance {variance:.3}");
+        let _ = write!(out, ", std dev {:.3}", variance.sqrt());
+    }
+    out.push_str("\npercentiles:");
+    for percent in PERCENTILES {
+        let rolled = stats.percentile(percent).unwrap_or_default();
+        let _ = write!(out, "  {percent}%: {rolled}");
+        let exact = exact.and_then(|exact| exact_percentile(exact, percent));
+        if let Some(exact) = exact.filter(|exact| *exact != rolled) {
+            let _ = write!(out, " (exact {exact})");
+        }
+    }
+
+    // rows from the lowest to highest total rolled, so long exploding
+    // tails don't swamp the histogram, with neighbouring totals sharing a
+    // row when there are too many for one each
+    let counts = stats.counts();
+    let (low, high) = (
+        *counts.keys().next().unwrap(),
+        *counts.keys().last().unwrap(),
+    );
+    let span = high.abs_diff(low).saturating_add(1);
+    let per_row = span.div_ceil(options.rows);
+    let rows: Vec<(String, f64, Option<f64>)> = (0..span.div_ceil(per_row))
+        .map(|row| {
+            let first = low + (row * per_row) as i64;
+            let last = high.min(first + (per_row - 1) as i64);
+            let label = if first == last {
+                first.to_string()
+            } else {
+                format!("{first}..{last}")
+            };
+            let count: u64 = counts.range(first..=last).map(|(_, n)| n).sum();
+            let frequency = count as f64 / stats.trials() as f64;
+            let exact_chance = exact.map(|e| e.range(first..=last).map(|(_, p)| p).sum());
+            (label, frequency, exact_chance)
+        })
+        .collect();
+    let tallest = rows
+        .iter()
+        .map(|(_, frequency, exact)| frequency.max(exact.unwrap_or(0.0)))
+        .fold(0.0, f64::max);
+    let label_width = rows
+        .iter()
+        .map(|(label, ..)| label.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    let _ = writeln!(out, "\n\n{:>label_width$}    rolled     exact", "total");
+    for (label, frequency, exact_chance) in rows {
+        let bar = "#".repeat((frequency / tallest * options.width as f64).round() as usize);
+        let exact_column = match exact_chance {
+            Some(chance) => format!("{:>8.3}%", chance * 100.0),
+            None => format!("{:>9}", "-"),
+        };
+        let _ = writeln!(
+            out,
+            "{label:>label_width$} {:>8.3}% {exact_column}  {bar}",
+            frequency * 100.0
+        );
+    }
+    out
+}
+
+fn csv(stats: &DiceStats, exact: Option<&BTreeMap<i64, f64>>) -> String {
+    let mut totals: Vec<i64> = stats.counts().keys().copied().collect();
+    if let Some(exact) = exact {
+        totals.extend(exact.keys());
+        totals.sort_unstable();
+        totals.dedup();
+    }
+    let mut out = String::from("total,count,frequency,exact\n");
+    for total in totals {
+        let count = stats.counts().get(&total).copied().unwrap_or(0);
+        let exact = exact
+            .map(|e| e.get(&total).copied().unwrap_or(0.0).to_string())
+            .unwrap_or_default();
+        let _ = writeln!(out, "{total},{count},{},{exact}", stats.frequency(total));
+    }
+    out
+}
+
+fn main() {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            exit(2);
+        }
+    };
+
+    // only needs to be mutable without the `locking` feature
+    #[allow(unused_mut)]
+    let mut rng = match options.seed {
+        Some(seed) => RandomNumberGenerator::seeded(seed),
+        None => RandomNumberGenerator::new(),
+    };
+    println!(
+        "{}: {} trials, seed {}",
+        options.dice,
+        options.trials,
+        rng.seed()
+    );
+    let stats = DiceStats::roll(&mut rng, &options.dice, options.trials);
+    let exact = options.dice.distribution();
+    if exact.is_none() {
+        println!("(no exact odds: exploding dice with a keep modifier, or too many dice)");
+    }
+    print!("{}", report(&options, &stats, exact.as_ref()));
+
+    if let Some(path) = &options.csv {
+        if let Err(err) = std::fs::write(path, csv(&stats, exact.as_ref())) {
+            eprintln!("error: can't write {}: {err}", path.display());
+            exit(1);
+        }
+        println!("wrote {}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(args: &str) -> Result<Options, String> {
+        parse_options(args.split_whitespace().map(String::from))
+    }
+
+    fn tally(totals: impl IntoIterator<Item = i64>) -> DiceStats {
+        let mut stats = DiceStats::new();
+        totals.into_iter().for_each(|total| stats.add(total));
+        stats
+    }
+
+    // the histogram's rows, after the header
+    fn histogram(report: &str) -> Vec<&str> {
+        report
+            .lines()
+            .skip_while(|line| !line.trim_start().starts_with("total"))
+            .skip(1)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let parsed = options("2d6 --trials 10 --seed 3 --width 20 --rows 5 --csv out.csv").unwrap();
+        assert_eq!(parsed.dice.to_string(), "2d6");
+        assert_eq!(
+            (parsed.trials, parsed.seed, parsed.width, parsed.rows),
+            (10, Some(3), 20, 5)
+        );
+        assert_eq!(parsed.csv, Some(PathBuf::from("out.csv")));
+
+        let defaults = options("3d6").unwrap();
+        assert_eq!((defaults.trials, defaults.seed), (100_000, None));
+        assert_eq!(
+            (defaults.width, defaults.rows, defaults.csv),
+            (50, 40, None)
+        );
+    }
+
+    #[test]
+    fn test_bad_options() {
+        assert_eq!(options("").err().unwrap(), "no dice given");
+        assert_eq!(options("3d6 --rows").err().unwrap(), "--rows needs a value");
+        assert_eq!(
+            options("3d6 --trials many").err().unwrap(),
+            "--trials takes a number, not \"many\""
+        );
+        assert_eq!(
+            options("3d6 --trials 0").err().unwrap(),
+            "--trials must be at least 1"
+        );
+        assert_eq!(
+            options("3d6 --rows 0").err().unwrap(),
+            "--rows must be at least 1"
+        );
+        assert_eq!(
+            options("3d6 --loud").err().unwrap(),
+            "unknown option --loud"
+        );
+        assert_eq!(
+            options("3d6 1d4").err().unwrap(),
+            "unexpected argument \"1d4\""
+        );
+        assert!(options("3x6").is_err());
+    }
+
+    #[test]
+    fn test_report_row_per_total() {
+        let options = options("1d4 --width 10").unwrap();
+        let stats = tally([1, 2, 2, 4]);
+        let exact = options.dice.distribution().unwrap();
+        let report = report(&options, &stats, Some(&exact));
+        assert!(report.contains("percentiles:  1%: 1"));
+        assert_eq!(
+            histogram(&report),
+            vec![
+                "     1   25.000%   25.000%  #####",
+                "     2   50.000%   25.000%  ##########",
+                "     3    0.000%   25.000%  ",
+                "     4   25.000%   25.000%  #####",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_shares_rows() {
+        let options = options("1d1000000 --rows 4").unwrap();
+        let stats = tally([1, 2, 500_000, 1_000_000]);
+        let report = report(&options, &stats, None);
+        let rows = histogram(&report);
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].starts_with("      1..250000   50.000%         -"));
+        assert!(rows[3].starts_with("750001..1000000   25.000%"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Desync detection: a generator can count the numbers it hands out and
+//! keep a rolling hash of them, so two runs that should match (a replay and
+//! the original, or two peers in a lockstep game) can be compared frame by
+//! frame to find where they drifted apart.
+use crate::{RandomNumberGenerator, algorithm::AlgorithmRng, seeding::splitmix64};
+use bevy::prelude::*;
+use rand::RngCore;
+use std::fmt;
+
+/// `DrawLog` summarizes everything a [`RandomNumberGenerator`] has drawn
+/// since [`start_draw_log`](RandomNumberGenerator::start_draw_log) was
+/// called. Two generators that drew the same values in the same order have
+/// equal logs; one extra, missing or different draw changes the checksum.
+///
+/// A draw is one 32- or 64-bit value from the underlying algorithm, or up to
+/// 8 bytes of a byte fill. A single call such as `range` or `shuffle` may
+/// make several draws. With the `locking` feature, draws from the
+/// generator's named streams (`RandomNumberGenerator::stream`) are counted
+/// too, each stream hashed on its own.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::RandomNumberGenerator;
+/// let mut original = RandomNumberGenerator::seeded(1);
+/// let mut replay = RandomNumberGenerator::seeded(1);
+/// original.start_draw_log();
+/// replay.start_draw_log();
+/// original.range(1..=6);
+/// replay.range(1..=6);
+/// assert_eq!(original.draw_log(), replay.draw_log());
+/// replay.range(1..=6);
+/// assert_ne!(original.draw_log(), replay.draw_log());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DrawLog {
+    /// How many draws were made.
+    pub draws: u64,
+    /// Rolling hash of every value drawn, in order.
+    pub checksum: u64,
+}
+
+impl DrawLog {
+    pub(crate) fn record(&mut self, value: u64) {
+        self.draws += 1;
+        self.checksum = mix(self.checksum, value);
+    }
+
+    /// Adds another stream's draws. Streams that drew nothing change nothing.
+    #[cfg(feature = "locking")]
+    pub(crate) fn merge(&mut self, other: DrawLog) {
+        if other.draws > 0 {
+            self.draws += other.draws;
+            self.checksum = mix(self.checksum, other.checksum);
+        }
+    }
+}
+
+/// Shows the log as `<draws> draws, checksum <16 hex digits>`.
+impl fmt::Display for DrawLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} draws, checksum {:016x}", self.draws, self.checksum)
+    }
+}
+
+// SplitMix64 is a bijection, so for a given checksum every value leads to a
+// different next checksum
+fn mix(checksum: u64, value: u64) -> u64 {
+    splitmix64(checksum ^ value)
+}
+
+/// Somewhere to record draws. Recording does nothing while the draw log is
+/// off.
+pub(crate) trait Record {
+    fn record(&mut self, value: u64);
+}
+
+impl Record for Option<&mut DrawLog> {
+    fn record(&mut self, value: u64) {
+        if let Some(log) = self {
+            log.record(value);
+        }
+    }
+}
+
+/// A generator's stream, with every value it produces passed to a log.
+/// All of [`RandomNumberGenerator`]'s draws go through one of these.
+pub(crate) struct Tracked<'a, L: Record> {
+    pub(crate) rng: &'a mut AlgorithmRng,
+    pub(crate) log: L,
+}
+
+impl<L: Record> Tracked<'_, L> {
+    fn record_bytes(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut value = [0; 8];
+            value[..chunk.len()].copy_from_slice(chunk);
+            self.log.record(u64::from_le_bytes(value));
+        }
+    }
+}
+
+impl<L: Record> RngCore for Tracked<'_, L> {
+    fn next_u32(&mut self) -> u32 {
+        let value = self.rng.next_u32();
+        self.log.record(value.into());
+        value
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let value = self.rng.next_u64();
+        self.log.record(value);
+        value
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+        self.record_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)?;
+        self.record_bytes(dest);
+        Ok(())
+    }
+}
+
+/// A Bevy system that logs the [`RandomNumberGenerator`] resource's
+/// [`DrawLog`] once a frame, numbering frames from when it first runs:
+///
+/// ```text
+/// frame 12: 3 draws, 240 total, checksum 5f0c2e9d81a4b7c3
+/// ```
+///
+/// Run the same seed and inputs twice and diff the logs: the first frame
+/// whose line differs is where the runs desynced. Add it to the `Last`
+/// schedule, after every system that draws, or let
+/// [`RandomPlugin::log_draws`](crate::RandomPlugin::log_draws) do it. It
+/// logs nothing while the draw log is off.
+pub fn log_draw_checksum(rng: Res<RandomNumberGenerator>, mut previous: Local<(u64, DrawLog)>) {
+    let Some(log) = rng.draw_log() else {
+        return;
+    };
+    let (frame, last) = &mut *previous;
+    *frame += 1;
+    info!(
+        "frame {frame}: {} draws, {} total, checksum {:016x}",
+        // the log may have been restarted since the last frame
+        log.draws.checked_sub(last.draws).unwrap_or(log.draws),
+        log.draws,
+        log.checksum
+    );
+    *last = log;
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(unused_mut, clippy::unnecessary_mut_passed))]
+mod test {
+    use super::*;
+    use crate::{Algorithm, RandomSource};
+
+    #[test]
+    fn test_off_by_default() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        assert_eq!(rng.draw_log(), None);
+        rng.start_draw_log();
+        assert_eq!(rng.draw_log(), Some(DrawLog::default()));
+        rng.stop_draw_log();
+        let _: u32 = rng.next();
+        assert_eq!(rng.draw_log(), None);
+    }
+
+    #[test]
+    fn test_matching_runs() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = (
+                RandomNumberGenerator::with_algorithm(algorithm, 1),
+                RandomNumberGenerator::with_algorithm(algorithm, 1),
+            );
+            rng.0.start_draw_log();
+            rng.1.start_draw_log();
+            for _ in 0..100 {
+                let _: u64 = rng.0.next();
+                let _: u64 = rng.1.next();
+                assert_eq!(rng.0.draw_log(), rng.1.draw_log());
+            }
+            assert_eq!(rng.0.draw_log().unwrap().draws, 100);
+        }
+    }
+
+    #[test]
+    fn test_desync() {
+        let mut rng = (
+            RandomNumberGenerator::seeded(2),
+            RandomNumberGenerator::seeded(2),
+        );
+        rng.0.start_draw_log();
+        rng.1.start_draw_log();
+        // an extra draw
+        let _: u64 = rng.0.next();
+        let _: u64 = rng.0.next();
+        let _: u64 = rng.1.next();
+        assert_ne!(rng.0.draw_log(), rng.1.draw_log());
+
+        // the same number of draws from a different position
+        let mut other = RandomNumberGenerator::seeded(3);
+        other.start_draw_log();
+        let _: u64 = other.next();
+        let _: u64 = other.next();
+        assert_eq!(other.draw_log().unwrap().draws, 2);
+        assert_ne!(other.draw_log(), rng.0.draw_log());
+    }
+
+    #[test]
+    fn test_every_draw_is_logged() {
+        let mut rng = RandomNumberGenerator::seeded(4);
+        rng.start_draw_log();
+        let mut bytes = [0; 20];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(rng.draw_log().unwrap().draws, 3);
+
+        // helpers and the `RandomSource` trait draw through the log too
+        let before = rng.draw_log().unwrap();
+        rng.shuffle(&mut [1, 2, 3, 4]);
+        rng.roll_dice("3d6").unwrap();
+        RandomSource::gaussian(&mut rng, 0.0, 1.0);
+        assert!(rng.draw_log().unwrap().draws > before.draws + 3 + 3);
+
+        // restarting counts from zero again
+        rng.start_draw_log();
+        assert_eq!(rng.draw_log(), Some(DrawLog::default()));
+    }
+
+    #[test]
+    fn test_log_system() {
+        let mut app = App::new();
+        let mut rng = RandomNumberGenerator::seeded(5);
+        rng.start_draw_log();
+        app.insert_resource(rng);
+        app.add_systems(Update, |mut rng: ResMut<RandomNumberGenerator>| {
+            let _: u32 = rng.next();
+        });
+        app.add_systems(Last, log_draw_checksum);
+        app.update();
+        app.update();
+        let log = app.world().resource::<RandomNumberGenerator>().draw_log();
+        assert_eq!(log.unwrap().draws, 2);
+    }
+
+    #[test]
+    fn test_display() {
+        let log = DrawLog {
+            draws: 3,
+            checksum: 0xbeef,
+        };
+        assert_eq!(log.to_string(), "3 draws, checksum 000000000000beef");
+    }
+}
@@ -0,0 +1,797 @@
+//! Dice notation (`3d6+2`, `4d6kh3`, `2d10!`, `1d20ro1`) parsing and rolling.
+use std::{fmt, str::FromStr};
+
+/// How many times a single die may explode before we stop rolling it.
+/// Stops pathological streaks from running (practically) forever.
+pub(crate) const MAX_EXPLOSIONS: usize = 100;
+
+/// How many times a single die may be rerolled. The last reroll only lands
+/// on faces above the threshold, which is where rerolling ends up anyway, so
+/// `1d1000000r999998` takes at most this many draws per die.
+pub const MAX_REROLLS: usize = 100;
+
+/// The most dice a single group may roll (`1000d6`).
+pub const MAX_DICE: u32 = 1000;
+
+/// The most sides a die may have (`1d1000000`).
+pub const MAX_SIDES: u32 = 1_000_000;
+
+/// Which dice of a group count towards its total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    /// Keep the `n` highest dice (`kh3`, or just `k3`).
+    Highest(u32),
+    /// Keep the `n` lowest dice (`kl1`).
+    Lowest(u32),
+}
+
+/// Rerolls dice that show `threshold` or lower.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reroll {
+    /// Reroll a low die once and keep the new result (`ro1`).
+    Once(u32),
+    /// Keep rerolling until the die shows more than the threshold (`r1`).
+    Always(u32),
+}
+
+/// A group of identical dice, such as `4d6kh3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dice {
+    /// Number of dice rolled.
+    pub count: u32,
+    /// Number of sides on each die.
+    pub sides: u32,
+    /// Dice that roll their highest face are rolled again, adding a new die (`!`).
+    pub explode: bool,
+    /// Optional reroll of low results.
+    pub reroll: Option<Reroll>,
+    /// Optional keep-highest/keep-lowest filter.
+    pub keep: Option<Keep>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Term {
+    Dice { negative: bool, dice: Dice },
+    Constant(i64),
+}
+
+/// A parsed dice expression: a sum of dice groups and constant modifiers.
+///
+/// Supported notation (case-insensitive, whitespace ignored):
+///
+/// * `NdS` - roll `N` dice with `S` sides (`N` defaults to 1, `d%` is `d100`).
+/// * `khK`/`kK` and `klK` - keep the `K` highest/lowest dice.
+/// * `!` - exploding dice: a die showing its highest face adds another die.
+/// * `rT` / `roT` - reroll dice showing `T` or lower (repeatedly / once).
+/// * `+`/`-` - add or subtract further dice groups or constant modifiers.
+///
+/// A group rolls at most [`MAX_DICE`] dice of at most [`MAX_SIDES`] sides.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::DiceExpr;
+/// let stats: DiceExpr = "4d6kh3".parse().unwrap();
+/// assert_eq!(stats.to_string(), "4d6kh3");
+/// assert_eq!((stats.min(), stats.max()), (3, 18));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceExpr {
+    terms: Vec<Term>,
+}
+
+/// A single die within a [`DiceRoll`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DieRoll {
+    /// The face showing on the die (after any rerolls).
+    pub face: u32,
+    /// Faces that were rolled and thrown away by a reroll modifier, at most
+    /// [`MAX_REROLLS`] of them.
+    pub rerolled: Vec<u32>,
+    /// `true` if this die was added because the previous die exploded.
+    pub exploded: bool,
+    /// `true` if the die counts towards the total after keep modifiers.
+    pub kept: bool,
+}
+
+/// The dice rolled for one group of a [`DiceExpr`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceGroupRoll {
+    /// The group that was rolled.
+    pub dice: Dice,
+    /// `true` if the group is subtracted from the total (`1d20-1d4`).
+    pub negative: bool,
+    /// Every die rolled, in the order they were rolled.
+    pub rolls: Vec<DieRoll>,
+    /// The sum of the kept dice (already negated for negative groups).
+    pub subtotal: i64,
+}
+
+/// The outcome of rolling a [`DiceExpr`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceRoll {
+    /// One entry per dice group, in expression order.
+    pub groups: Vec<DiceGroupRoll>,
+    /// The sum of all constant modifiers.
+    pub modifier: i64,
+    /// The final result.
+    pub total: i64,
+}
+
+/// Reasons a dice expression can fail to parse. Positions are byte offsets
+/// into the original string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiceParseError {
+    /// The expression contained no dice or numbers.
+    Empty,
+    /// A character that does not belong at this point of the expression.
+    UnexpectedChar { position: usize, found: char },
+    /// The expression stopped in the middle of a term (e.g. `3d`).
+    UnexpectedEnd,
+    /// A number did not fit in the supported range.
+    NumberTooLarge { position: usize },
+    /// A dice group rolls zero dice (`0d6`).
+    NoDice { position: usize },
+    /// A die has zero sides (`1d0`).
+    NoSides { position: usize },
+    /// A dice group rolls more than [`MAX_DICE`] dice.
+    TooManyDice { position: usize, count: u32 },
+    /// A die has more than [`MAX_SIDES`] sides.
+    TooManySides { position: usize, sides: u32 },
+    /// The same modifier was given twice for one group (`4d6kh3kl1`).
+    DuplicateModifier { position: usize },
+    /// A keep modifier keeps more dice than are rolled (`2d6kh3`).
+    KeepTooMany {
+        position: usize,
+        keep: u32,
+        count: u32,
+    },
+    /// A reroll threshold would reroll every face (`1d6r6`).
+    RerollEverything {
+        position: usize,
+        threshold: u32,
+        sides: u32,
+    },
+    /// A one-sided die always shows its highest face, so it cannot explode.
+    ExplodeOneSided { position: usize },
+}
+
+impl fmt::Display for DiceParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty dice expression"),
+            Self::UnexpectedChar { position, found } => {
+                write!(f, "unexpected '{found}' at position {position}")
+            }
+            Self::UnexpectedEnd => write!(f, "dice expression ended unexpectedly"),
+            Self::NumberTooLarge { position } => {
+                write!(f, "number at position {position} is too large")
+            }
+            Self::NoDice { position } => write!(f, "no dice rolled at position {position}"),
+            Self::NoSides { position } => write!(f, "zero-sided die at position {position}"),
+            Self::TooManyDice { position, count } => write!(
+                f,
+                "cannot roll {count} dice, the limit is {MAX_DICE} (position {position})"
+            ),
+            Self::TooManySides { position, sides } => write!(
+                f,
+                "a die cannot have {sides} sides, the limit is {MAX_SIDES} (position {position})"
+            ),
+            Self::DuplicateModifier { position } => {
+                write!(f, "duplicate modifier at position {position}")
+            }
+            Self::KeepTooMany {
+                position,
+                keep,
+                count,
+            } => write!(
+                f,
+                "cannot keep {keep} of {count} dice (position {position})"
+            ),
+            Self::RerollEverything {
+                position,
+                threshold,
+                sides,
+            } => write!(
+                f,
+                "rerolling {threshold} or lower on a d{sides} rerolls every face (position {position})"
+            ),
+            Self::ExplodeOneSided { position } => {
+                write!(f, "a one-sided die cannot explode (position {position})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiceParseError {}
+
+impl Dice {
+    fn min(&self) -> i64 {
+        let counted = match self.keep {
+            Some(Keep::Highest(n) | Keep::Lowest(n)) => n,
+            None => self.count,
+        };
+        let lowest_face = match self.reroll {
+            Some(Reroll::Always(threshold)) => threshold.saturating_add(1),
+            _ => 1,
+        };
+        i64::from(counted).saturating_mul(i64::from(lowest_face))
+    }
+
+    fn max(&self) -> i64 {
+        // Explosions add extra dice, so a keep modifier caps the count
+        let counted = match self.keep {
+            Some(Keep::Highest(n) | Keep::Lowest(n)) => i64::from(n),
+            None if self.explode => i64::from(self.count).saturating_mul(MAX_EXPLOSIONS as i64 + 1),
+            None => i64::from(self.count),
+        };
+        counted.saturating_mul(i64::from(self.sides))
+    }
+
+    fn roll_die(&self, die: &mut impl FnMut(u32) -> u32, exploded: bool) -> DieRoll {
+        let mut face = die(self.sides);
+        let mut rerolled = Vec::new();
+        match self.reroll {
+            Some(Reroll::Once(threshold)) if face <= threshold => {
+                rerolled.push(face);
+                face = die(self.sides);
+            }
+            Some(Reroll::Always(threshold)) => {
+                while face <= threshold {
+                    rerolled.push(face);
+                    face = if rerolled.len() < MAX_REROLLS {
+                        die(self.sides)
+                    } else {
+                        // as likely as any face the rerolls could stop on
+                        threshold + die(self.sides - threshold)
+                    };
+                }
+            }
+            _ => {}
+        }
+        DieRoll {
+            face,
+            rerolled,
+            exploded,
+            kept: true,
+        }
+    }
+
+    fn roll_with(&self, die: &mut impl FnMut(u32) -> u32) -> Vec<DieRoll> {
+        let mut rolls = Vec::with_capacity(self.count as usize);
+        for _ in 0..self.count {
+            let mut roll = self.roll_die(die, false);
+            let mut explosions = 0;
+            while self.explode && roll.face == self.sides && explosions < MAX_EXPLOSIONS {
+                rolls.push(roll);
+                roll = self.roll_die(die, true);
+                explosions += 1;
+            }
+            rolls.push(roll);
+        }
+
+        if let Some(keep) = self.keep {
+            // Stable sort of indices, so ties are resolved in roll order
+            let mut order: Vec<usize> = (0..rolls.len()).collect();
+            let kept = match keep {
+                Keep::Highest(n) => {
+                    order.sort_by(|a, b| rolls[*b].face.cmp(&rolls[*a].face));
+                    n
+                }
+                Keep::Lowest(n) => {
+                    order.sort_by(|a, b| rolls[*a].face.cmp(&rolls[*b].face));
+                    n
+                }
+            };
+            order
+                .iter()
+                .skip(kept as usize)
+                .for_each(|i| rolls[*i].kept = false);
+        }
+        rolls
+    }
+}
+
+impl DiceExpr {
+    /// The lowest total this expression can produce.
+    pub fn min(&self) -> i64 {
+        self.terms
+            .iter()
+            .map(|term| match term {
+                Term::Dice {
+                    negative: false,
+                    dice,
+                } => dice.min(),
+                Term::Dice {
+                    negative: true,
+                    dice,
+                } => -dice.max(),
+                Term::Constant(n) => *n,
+            })
+            .fold(0, i64::saturating_add)
+    }
+
+    /// The highest total this expression can produce. Exploding dice are
+    /// capped at the number of explosions a single die is allowed.
+    pub fn max(&self) -> i64 {
+        self.terms
+            .iter()
+            .map(|term| match term {
+                Term::Dice {
+                    negative: false,
+                    dice,
+                } => dice.max(),
+                Term::Dice {
+                    negative: true,
+                    dice,
+                } => -dice.min(),
+                Term::Constant(n) => *n,
+            })
+            .fold(0, i64::saturating_add)
+    }
+
+    /// The dice groups in this expression, with `true` for subtracted groups.
+    pub fn dice(&self) -> impl Iterator<Item = (bool, &Dice)> {
+        self.terms.iter().filter_map(|term| match term {
+            Term::Dice { negative, dice } => Some((*negative, dice)),
+            Term::Constant(_) => None,
+        })
+    }
+
+    /// The sum of all constant modifiers in this expression.
+    pub fn modifier(&self) -> i64 {
+        self.terms
+            .iter()
+            .map(|term| match term {
+                Term::Constant(n) => *n,
+                Term::Dice { .. } => 0,
+            })
+            .sum()
+    }
+
+    /// Rolls the expression, asking `die` for a face in `1..=sides` for each die.
+    pub(crate) fn roll_with(&self, mut die: impl FnMut(u32) -> u32) -> DiceRoll {
+        let mut groups = Vec::new();
+        let mut modifier = 0;
+        for term in self.terms.iter() {
+            match term {
+                Term::Dice { negative, dice } => {
+                    let rolls = dice.roll_with(&mut die);
+                    let sum: i64 = rolls
+                        .iter()
+                        .filter(|roll| roll.kept)
+                        .map(|roll| i64::from(roll.face))
+                        .sum();
+                    groups.push(DiceGroupRoll {
+                        dice: *dice,
+                        negative: *negative,
+                        rolls,
+                        subtotal: if *negative { -sum } else { sum },
+                    });
+                }
+                Term::Constant(n) => modifier += n,
+            }
+        }
+        let total = groups.iter().map(|g| g.subtotal).sum::<i64>() + modifier;
+        DiceRoll {
+            groups,
+            modifier,
+            total,
+        }
+    }
+}
+
+impl DiceRoll {
+    /// Every die rolled, across all groups.
+    pub fn dice(&self) -> impl Iterator<Item = &DieRoll> {
+        self.groups.iter().flat_map(|group| group.rolls.iter())
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    len: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<(usize, char)> {
+        self.skip_whitespace();
+        self.chars.peek().map(|(i, c)| (*i, c.to_ascii_lowercase()))
+    }
+
+    fn position(&mut self) -> usize {
+        self.peek().map(|(i, _)| i).unwrap_or(self.len)
+    }
+
+    fn eat(&mut self, wanted: char) -> bool {
+        if self.peek().map(|(_, c)| c) == Some(wanted) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&mut self) -> DiceParseError {
+        match self.peek() {
+            Some((position, _)) => DiceParseError::UnexpectedChar {
+                position,
+                found: self.chars.peek().map(|(_, c)| *c).unwrap_or_default(),
+            },
+            None => DiceParseError::UnexpectedEnd,
+        }
+    }
+
+    fn number(&mut self) -> Result<Option<u32>, DiceParseError> {
+        let position = self.position();
+        let mut value: Option<u32> = None;
+        while let Some((_, c)) = self.chars.peek() {
+            let Some(digit) = c.to_digit(10) else {
+                break;
+            };
+            self.chars.next();
+            value = Some(
+                value
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add(digit))
+                    .ok_or(DiceParseError::NumberTooLarge { position })?,
+            );
+        }
+        Ok(value)
+    }
+
+    fn required_number(&mut self) -> Result<u32, DiceParseError> {
+        self.skip_whitespace();
+        match self.number()? {
+            Some(n) => Ok(n),
+            None => Err(self.unexpected()),
+        }
+    }
+
+    fn term(&mut self, negative: bool) -> Result<Term, DiceParseError> {
+        let start = self.position();
+        let count = self.number()?;
+        if !self.eat('d') {
+            return match count {
+                Some(n) if negative => Ok(Term::Constant(-i64::from(n))),
+                Some(n) => Ok(Term::Constant(i64::from(n))),
+                None => Err(self.unexpected()),
+            };
+        }
+        let count = count.unwrap_or(1);
+        if count == 0 {
+            return Err(DiceParseError::NoDice { position: start });
+        }
+        if count > MAX_DICE {
+            return Err(DiceParseError::TooManyDice {
+                position: start,
+                count,
+            });
+        }
+        let sides_position = self.position();
+        let sides = if self.eat('%') {
+            100
+        } else {
+            self.required_number()?
+        };
+        if sides == 0 {
+            return Err(DiceParseError::NoSides {
+                position: sides_position,
+            });
+        }
+        if sides > MAX_SIDES {
+            return Err(DiceParseError::TooManySides {
+                position: sides_position,
+                sides,
+            });
+        }
+        let mut dice = Dice {
+            count,
+            sides,
+            explode: false,
+            reroll: None,
+            keep: None,
+        };
+        loop {
+            let position = self.position();
+            if self.eat('!') {
+                if dice.explode {
+                    return Err(DiceParseError::DuplicateModifier { position });
+                }
+                if sides == 1 {
+                    return Err(DiceParseError::ExplodeOneSided { position });
+                }
+                dice.explode = true;
+            } else if self.eat('k') {
+                if dice.keep.is_some() {
+                    return Err(DiceParseError::DuplicateModifier { position });
+                }
+                let lowest = if self.eat('l') {
+                    true
+                } else {
+                    self.eat('h');
+                    false
+                };
+                let keep = self.required_number()?;
+                if keep > count {
+                    return Err(DiceParseError::KeepTooMany {
+                        position,
+                        keep,
+                        count,
+                    });
+                }
+                dice.keep = Some(if lowest {
+                    Keep::Lowest(keep)
+                } else {
+                    Keep::Highest(keep)
+                });
+            } else if self.eat('r') {
+                if dice.reroll.is_some() {
+                    return Err(DiceParseError::DuplicateModifier { position });
+                }
+                let once = self.eat('o');
+                let threshold = self.required_number()?;
+                if threshold >= sides {
+                    return Err(DiceParseError::RerollEverything {
+                        position,
+                        threshold,
+                        sides,
+                    });
+                }
+                dice.reroll = Some(if once {
+                    Reroll::Once(threshold)
+                } else {
+                    Reroll::Always(threshold)
+                });
+            } else {
+                break;
+            }
+        }
+        Ok(Term::Dice { negative, dice })
+    }
+}
+
+impl FromStr for DiceExpr {
+    type Err = DiceParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.char_indices().peekable(),
+            len: s.len(),
+        };
+        if parser.peek().is_none() {
+            return Err(DiceParseError::Empty);
+        }
+        let mut terms = Vec::new();
+        let mut negative = parser.eat('-');
+        loop {
+            terms.push(parser.term(negative)?);
+            if parser.eat('+') {
+                negative = false;
+            } else if parser.eat('-') {
+                negative = true;
+            } else if parser.peek().is_none() {
+                break;
+            } else {
+                return Err(parser.unexpected());
+            }
+        }
+        Ok(Self { terms })
+    }
+}
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        if self.explode {
+            write!(f, "!")?;
+        }
+        match self.reroll {
+            Some(Reroll::Once(n)) => write!(f, "ro{n}")?,
+            Some(Reroll::Always(n)) => write!(f, "r{n}")?,
+            None => {}
+        }
+        match self.keep {
+            Some(Keep::Highest(n)) => write!(f, "kh{n}"),
+            Some(Keep::Lowest(n)) => write!(f, "kl{n}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for DiceExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            let (negative, body) = match term {
+                Term::Dice { negative, dice } => (*negative, dice.to_string()),
+                Term::Constant(n) => (*n < 0, n.unsigned_abs().to_string()),
+            };
+            match (i, negative) {
+                (0, false) => {}
+                (_, true) => write!(f, "-")?,
+                (_, false) => write!(f, "+")?,
+            }
+            write!(f, "{body}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Hands out faces from a fixed list, so rolls can be checked exactly.
+    fn scripted(faces: &[u32]) -> impl FnMut(u32) -> u32 + '_ {
+        let mut faces = faces.iter();
+        move |_| *faces.next().expect("ran out of scripted faces")
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for notation in [
+            "3d6+2",
+            "4d6kh3",
+            "2d20kl1",
+            "10d6!",
+            "1d20ro1",
+            "2d8r2-1d4+7",
+        ] {
+            let expr: DiceExpr = notation.parse().unwrap();
+            assert_eq!(expr.to_string(), notation);
+        }
+        let expr: DiceExpr = " D% + 4d6 K3 ".parse().unwrap();
+        assert_eq!(expr.to_string(), "1d100+4d6kh3");
+        let expr: DiceExpr = "-2+1d4".parse().unwrap();
+        assert_eq!(expr.to_string(), "-2+1d4");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |s: &str| s.parse::<DiceExpr>().unwrap_err();
+        assert_eq!(err(""), DiceParseError::Empty);
+        assert_eq!(err("  "), DiceParseError::Empty);
+        assert_eq!(err("3d"), DiceParseError::UnexpectedEnd);
+        assert_eq!(err("3d6+"), DiceParseError::UnexpectedEnd);
+        assert_eq!(
+            err("3x6"),
+            DiceParseError::UnexpectedChar {
+                position: 1,
+                found: 'x'
+            }
+        );
+        assert_eq!(err("0d6"), DiceParseError::NoDice { position: 0 });
+        assert_eq!(err("2d0"), DiceParseError::NoSides { position: 2 });
+        assert_eq!(
+            err("99999999999d6"),
+            DiceParseError::NumberTooLarge { position: 0 }
+        );
+        assert_eq!(
+            err("2d6kh3"),
+            DiceParseError::KeepTooMany {
+                position: 3,
+                keep: 3,
+                count: 2
+            }
+        );
+        assert_eq!(
+            err("4d6kh3kl1"),
+            DiceParseError::DuplicateModifier { position: 6 }
+        );
+        assert_eq!(
+            err("1d6r6"),
+            DiceParseError::RerollEverything {
+                position: 3,
+                threshold: 6,
+                sides: 6
+            }
+        );
+        assert_eq!(err("3d1!"), DiceParseError::ExplodeOneSided { position: 3 });
+        assert_eq!(
+            err("4000000000d6"),
+            DiceParseError::TooManyDice {
+                position: 0,
+                count: 4_000_000_000
+            }
+        );
+        assert_eq!(
+            err("4294967295d4294967295!"),
+            DiceParseError::TooManyDice {
+                position: 0,
+                count: u32::MAX
+            }
+        );
+        assert_eq!(
+            err("1d4294967295!"),
+            DiceParseError::TooManySides {
+                position: 2,
+                sides: u32::MAX
+            }
+        );
+        assert!("1000d1000000!".parse::<DiceExpr>().is_ok());
+    }
+
+    #[test]
+    fn test_keep_highest() {
+        let expr: DiceExpr = "4d6kh3+1".parse().unwrap();
+        let roll = expr.roll_with(scripted(&[3, 6, 1, 4]));
+        assert_eq!(roll.total, 3 + 6 + 4 + 1);
+        assert_eq!(roll.modifier, 1);
+        let kept: Vec<bool> = roll.dice().map(|d| d.kept).collect();
+        assert_eq!(kept, vec![true, true, false, true]);
+    }
+
+    #[test]
+    fn test_keep_lowest_and_subtract() {
+        let expr: DiceExpr = "10-2d20kl1".parse().unwrap();
+        let roll = expr.roll_with(scripted(&[17, 5]));
+        assert_eq!(roll.total, 5);
+        assert_eq!(roll.groups[0].subtotal, -5);
+    }
+
+    #[test]
+    fn test_explode() {
+        let expr: DiceExpr = "2d6!".parse().unwrap();
+        let roll = expr.roll_with(scripted(&[6, 6, 2, 3]));
+        assert_eq!(roll.total, 17);
+        let exploded: Vec<bool> = roll.dice().map(|d| d.exploded).collect();
+        assert_eq!(exploded, vec![false, true, true, false]);
+    }
+
+    #[test]
+    fn test_reroll() {
+        let once: DiceExpr = "1d6ro2".parse().unwrap();
+        let roll = once.roll_with(scripted(&[1, 2]));
+        assert_eq!(roll.total, 2);
+        assert_eq!(roll.groups[0].rolls[0].rerolled, vec![1]);
+
+        let always: DiceExpr = "1d6r2".parse().unwrap();
+        let roll = always.roll_with(scripted(&[1, 2, 5]));
+        assert_eq!(roll.total, 5);
+        assert_eq!(roll.groups[0].rolls[0].rerolled, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_reroll_limit() {
+        // valid, but would take about 500k rerolls per die
+        let expr: DiceExpr = "1000d1000000r999998".parse().unwrap();
+        let mut draws = 0;
+        let roll = expr.roll_with(|sides| {
+            draws += 1;
+            if sides == MAX_SIDES { 1 } else { sides }
+        });
+        assert_eq!(draws, 1000 * (MAX_REROLLS + 1));
+        assert!(roll.dice().all(|d| d.rerolled.len() == MAX_REROLLS));
+        assert!(roll.dice().all(|d| d.face == MAX_SIDES));
+        assert_eq!(roll.total, expr.max());
+    }
+
+    #[test]
+    fn test_min_max() {
+        let expr: DiceExpr = "3d6+2".parse().unwrap();
+        assert_eq!((expr.min(), expr.max()), (5, 20));
+        let expr: DiceExpr = "1d20-1d4".parse().unwrap();
+        assert_eq!((expr.min(), expr.max()), (-3, 19));
+        let expr: DiceExpr = "2d6r2".parse().unwrap();
+        assert_eq!(expr.min(), 6);
+        let expr: DiceExpr = "3d6!kh2".parse().unwrap();
+        assert_eq!(expr.max(), 12);
+        let expr: DiceExpr = "1000d1000000!-1000d1000000r999999".parse().unwrap();
+        assert_eq!(expr.max(), 1000 * 101 * 1_000_000 - 1000 * 1_000_000);
+        // the fields are public, so groups past the parser's limits saturate
+        let huge = Dice {
+            count: u32::MAX,
+            sides: u32::MAX,
+            explode: true,
+            reroll: Some(Reroll::Always(u32::MAX - 1)),
+            keep: None,
+        };
+        assert_eq!(huge.max(), i64::MAX);
+        assert_eq!(huge.min(), i64::MAX);
+    }
+}
@@ -0,0 +1,442 @@
+//! Exact and rolled probability distributions of dice expressions, for
+//! tuning game odds.
+use crate::{Dice, DiceExpr, Keep, RandomSource, Reroll, dice::MAX_EXPLOSIONS};
+use std::collections::BTreeMap;
+
+/// Exploding dice stop being followed once a chain of explosions is less
+/// likely than this.
+const NEGLIGIBLE: f64 = 1e-15;
+
+/// The most steps (roughly, multiplications) [`DiceExpr::distribution`]
+/// will take on, about a second's work. Expressions that would need more,
+/// such as `100d1000` or `1000d100kh10`, have no exact odds.
+pub const MAX_DISTRIBUTION_WORK: u64 = 100_000_000;
+
+/// Probabilities of consecutive totals, starting at `offset`.
+struct Odds {
+    offset: i64,
+    chances: Vec<f64>,
+}
+
+impl Odds {
+    fn constant(value: i64) -> Self {
+        Self {
+            offset: value,
+            chances: vec![1.0],
+        }
+    }
+
+    /// The odds of the sum of two independent results.
+    fn add(&self, other: &Self) -> Self {
+        let mut chances = vec![0.0; self.chances.len() + other.chances.len() - 1];
+        for (i, a) in self.chances.iter().enumerate() {
+            for (j, b) in other.chances.iter().enumerate() {
+                chances[i + j] += a * b;
+            }
+        }
+        Self {
+            offset: self.offset + other.offset,
+            chances,
+        }
+    }
+
+    fn negate(mut self) -> Self {
+        self.chances.reverse();
+        Self {
+            offset: -(self.offset + self.chances.len() as i64 - 1),
+            chances: self.chances,
+        }
+    }
+}
+
+/// The chance of each face of a single die, after rerolls, indexed by face.
+fn face_odds(dice: &Dice) -> Vec<f64> {
+    let sides = dice.sides as usize;
+    let fair = 1.0 / sides as f64;
+    let mut odds = vec![0.0; sides + 1];
+    match dice.reroll {
+        None => odds[1..].fill(fair),
+        Some(Reroll::Once(threshold)) => {
+            // a low face is rerolled once, and the reroll is fair
+            let rerolled = threshold as usize as f64 * fair;
+            for (face, chance) in odds.iter_mut().enumerate().skip(1) {
+                *chance = rerolled * fair + if face > threshold as usize { fair } else { 0.0 };
+            }
+        }
+        Some(Reroll::Always(threshold)) => {
+            odds[threshold as usize + 1..].fill(1.0 / (sides - threshold as usize) as f64);
+        }
+    }
+    odds
+}
+
+/// The total of one die and every die its explosions add.
+fn die_odds(dice: &Dice, faces: &[f64]) -> Vec<f64> {
+    let sides = faces.len() - 1;
+    let mut odds = Vec::new();
+    let mut chain = 1.0;
+    for explosions in 0..=MAX_EXPLOSIONS {
+        let base = explosions * sides;
+        odds.resize(base + sides + 1, 0.0);
+        for face in 1..sides {
+            odds[base + face] += chain * faces[face];
+        }
+        if explosions == MAX_EXPLOSIONS || !dice.explode {
+            odds[base + sides] += chain * faces[sides];
+            break;
+        }
+        chain *= faces[sides];
+        if chain < NEGLIGIBLE {
+            break;
+        }
+    }
+    odds
+}
+
+/// The total of the kept dice, found by deciding how many dice show each
+/// face, from the first face kept to the last.
+fn kept_odds(dice: &Dice, faces: &[f64], keep: Keep) -> Vec<f64> {
+    let count = dice.count as usize;
+    let sides = faces.len() - 1;
+    let (kept, order): (usize, Vec<usize>) = match keep {
+        Keep::Highest(n) => (n as usize, (1..=sides).rev().collect()),
+        Keep::Lowest(n) => (n as usize, (1..=sides).collect()),
+    };
+    // states[dice placed so far][total of the kept ones]
+    let mut states = vec![vec![0.0; kept * sides + 1]; count + 1];
+    states[0][0] = 1.0;
+    for face in order {
+        let mut next = vec![vec![0.0; kept * sides + 1]; count + 1];
+        for (placed, totals) in states.iter().enumerate() {
+            for (total, chance) in totals.iter().enumerate().filter(|(_, c)| **c > 0.0) {
+                // chance that `showing` of the remaining dice show this face:
+                // (remaining choose showing) * p^showing
+                let mut weight = 1.0;
+                for showing in 0..=count - placed {
+                    if showing > 0 {
+                        weight *=
+                            (count - placed - showing + 1) as f64 / showing as f64 * faces[face];
+                        if weight == 0.0 {
+                            break;
+                        }
+                    }
+                    let newly_kept = (placed + showing).min(kept) - placed.min(kept);
+                    next[placed + showing][total + face * newly_kept] += chance * weight;
+                }
+            }
+        }
+        states = next;
+    }
+    states.swap_remove(count)
+}
+
+/// The odds of one group of dice, or `None` if they have no exact form or
+/// working them out would take more than `MAX_DISTRIBUTION_WORK` steps.
+fn group_odds(dice: &Dice) -> Option<Odds> {
+    let count = u64::from(dice.count);
+    let sides = u64::from(dice.sides);
+    let chances = match dice.keep {
+        // explosions add dice to the pool being kept from
+        Some(_) if dice.explode => return None,
+        Some(keep @ (Keep::Highest(kept) | Keep::Lowest(kept))) => {
+            // every face, placed count, kept total and number showing
+            let totals = u64::from(kept.min(dice.count)) * sides + 1;
+            let work = [sides, count + 1, totals, count + 1]
+                .into_iter()
+                .try_fold(1_u64, u64::checked_mul);
+            if work.is_none_or(|work| work > MAX_DISTRIBUTION_WORK) {
+                return None;
+            }
+            kept_odds(dice, &face_odds(dice), keep)
+        }
+        None => {
+            let die = Odds {
+                offset: 0,
+                chances: die_odds(dice, &face_odds(dice)),
+            };
+            // adding each die multiplies out the running sum, which grows
+            // by all but one of a die's totals each time
+            let length = die.chances.len() as u64;
+            let work = (count * count.saturating_sub(1) / 2)
+                .checked_mul(length - 1)
+                .and_then(|work| work.checked_add(count))
+                .and_then(|work| work.checked_mul(length));
+            if work.is_none_or(|work| work > MAX_DISTRIBUTION_WORK) {
+                return None;
+            }
+            let mut sum = Odds::constant(0);
+            (0..dice.count).for_each(|_| sum = sum.add(&die));
+            sum.chances
+        }
+    };
+    Some(Odds { offset: 0, chances })
+}
+
+impl DiceExpr {
+    /// The exact chance of every total this expression can produce, or
+    /// `None` for exploding dice with a keep modifier (`3d6!kh2`), which
+    /// have no practical exact form, and for expressions that would take
+    /// more than [`MAX_DISTRIBUTION_WORK`] steps to work out, such as
+    /// `100d1000`. `1000d6` and `20d100` are well within the limit.
+    ///
+    /// Chains of explosions rarer than one in 10¹⁵ are left out, so the
+    /// chances of exploding dice add up to very slightly under 1.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use my_library::DiceExpr;
+    /// let advantage: DiceExpr = "2d20kh1".parse().unwrap();
+    /// let odds = advantage.distribution().unwrap();
+    /// assert!((odds[&20] - 39.0 / 400.0).abs() < 1e-12);
+    /// ```
+    pub fn distribution(&self) -> Option<BTreeMap<i64, f64>> {
+        let mut total = Odds::constant(self.modifier());
+        for (negative, dice) in self.dice() {
+            let odds = group_odds(dice)?;
+            let work = (total.chances.len() as u64).checked_mul(odds.chances.len() as u64)?;
+            if work > MAX_DISTRIBUTION_WORK {
+                return None;
+            }
+            total = total.add(&if negative { odds.negate() } else { odds });
+        }
+        Some(
+            (total.offset..)
+                .zip(total.chances)
+                .filter(|(_, chance)| *chance > 0.0)
+                .collect(),
+        )
+    }
+}
+
+/// `DiceStats` tallies rolled totals, and summarizes them.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{DiceStats, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let stats = DiceStats::roll(&mut rng, &"3d6".parse().unwrap(), 10_000);
+/// assert!((stats.mean() - 10.5).abs() < 0.1);
+/// assert_eq!(stats.percentile(50.0), Some(10));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiceStats {
+    counts: BTreeMap<i64, u64>,
+    trials: u64,
+}
+
+impl DiceStats {
+    /// Creates an empty tally.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rolls `dice` `trials` times and tallies the totals.
+    pub fn roll(mut rng: impl RandomSource, dice: &DiceExpr, trials: u64) -> Self {
+        let mut stats = Self::new();
+        (0..trials).for_each(|_| stats.add(rng.roll(dice).total));
+        stats
+    }
+
+    /// Tallies one total.
+    pub fn add(&mut self, total: i64) {
+        *self.counts.entry(total).or_default() += 1;
+        self.trials += 1;
+    }
+
+    /// Number of totals tallied.
+    pub fn trials(&self) -> u64 {
+        self.trials
+    }
+
+    /// How many times each total came up, lowest total first.
+    pub fn counts(&self) -> &BTreeMap<i64, u64> {
+        &self.counts
+    }
+
+    /// The fraction of trials that came up `total`.
+    pub fn frequency(&self, total: i64) -> f64 {
+        let count = self.counts.get(&total).copied().unwrap_or(0);
+        count as f64 / self.trials.max(1) as f64
+    }
+
+    /// The average total, or `NaN` if nothing was tallied.
+    pub fn mean(&self) -> f64 {
+        let sum: f64 = self
+            .counts
+            .iter()
+            .map(|(total, count)| *total as f64 * *count as f64)
+            .sum();
+        sum / self.trials as f64
+    }
+
+    /// The variance of the totals, or `NaN` if nothing was tallied.
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+        let sum: f64 = self
+            .counts
+            .iter()
+            .map(|(total, count)| (*total as f64 - mean).powi(2) * *count as f64)
+            .sum();
+        sum / self.trials as f64
+    }
+
+    /// The lowest total that at least `percent`% of trials came in at or
+    /// under, or `None` if nothing was tallied. `0.0` gives the lowest total
+    /// and `100.0` the highest.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `percent` is within `0.0..=100.0`.
+    pub fn percentile(&self, percent: f64) -> Option<i64> {
+        assert!(
+            (0.0..=100.0).contains(&percent),
+            "percentile {percent} is not within 0..=100"
+        );
+        let wanted = (percent / 100.0 * self.trials as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        self.counts.iter().find_map(|(total, count)| {
+            seen += count;
+            (seen >= wanted).then_some(*total)
+        })
+    }
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(clippy::unnecessary_mut_passed))]
+mod test {
+    use super::*;
+    use crate::RandomNumberGenerator;
+
+    fn odds(notation: &str) -> BTreeMap<i64, f64> {
+        notation
+            .parse::<DiceExpr>()
+            .unwrap()
+            .distribution()
+            .unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    fn mean(odds: &BTreeMap<i64, f64>) -> f64 {
+        odds.iter()
+            .map(|(total, chance)| *total as f64 * chance)
+            .sum()
+    }
+
+    #[test]
+    fn test_sums() {
+        let three_d6 = odds("3d6");
+        assert_eq!(three_d6.len(), 16);
+        assert_close(three_d6[&3], 1.0 / 216.0);
+        assert_close(three_d6[&10], 27.0 / 216.0);
+        assert_close(three_d6.values().sum(), 1.0);
+        assert_close(mean(&three_d6), 10.5);
+
+        let expr: DiceExpr = "1d20-1d4+2".parse().unwrap();
+        let mixed = expr.distribution().unwrap();
+        assert_eq!(*mixed.keys().next().unwrap(), expr.min());
+        assert_eq!(*mixed.keys().last().unwrap(), expr.max());
+        assert_close(mean(&mixed), 10.5 - 2.5 + 2.0);
+    }
+
+    #[test]
+    fn test_keep() {
+        let stats = odds("4d6kh3");
+        assert_close(stats[&18], 21.0 / 1296.0);
+        assert_close(stats[&3], 1.0 / 1296.0);
+        assert_close(mean(&stats), 15869.0 / 1296.0);
+        assert_close(odds("2d20kh1")[&20], 39.0 / 400.0);
+        assert_close(odds("2d20kl1")[&1], 39.0 / 400.0);
+        assert_close(odds("2d20kl1")[&20], 1.0 / 400.0);
+    }
+
+    #[test]
+    fn test_reroll_and_explode() {
+        let once = odds("1d6ro1");
+        assert_close(once[&1], 1.0 / 36.0);
+        assert_close(once[&6], 7.0 / 36.0);
+        let always = odds("1d6r2");
+        assert_eq!(always.len(), 4);
+        assert_close(always[&3], 0.25);
+
+        let exploding = odds("1d6!");
+        assert!(!exploding.contains_key(&6));
+        assert_close(exploding[&7], 1.0 / 36.0);
+        assert_close(mean(&exploding), 3.5 * 6.0 / 5.0);
+
+        let pool: DiceExpr = "3d6!kh2".parse().unwrap();
+        assert_eq!(pool.distribution(), None);
+    }
+
+    #[test]
+    fn test_work_limit() {
+        assert_close(odds("1000d6").values().sum(), 1.0);
+        assert_close(mean(&odds("20d100")), 20.0 * 50.5);
+        assert_close(odds("10d100kh3").values().sum(), 1.0);
+        assert_close(odds("1d1000000").values().sum(), 1.0);
+        for notation in [
+            "100d1000",
+            "1000d1000000",
+            "1000d1000000!",
+            "1000d100kh10",
+            "1000d1000000kl1000",
+            "1d1000000+1d1000000",
+        ] {
+            let expr: DiceExpr = notation.parse().unwrap();
+            assert_eq!(expr.distribution(), None, "{notation}");
+        }
+    }
+
+    #[test]
+    fn test_rolled_matches_exact() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        for notation in ["4d6kh3", "2d8ro2+1d4-3", "2d6!"] {
+            let expr: DiceExpr = notation.parse().unwrap();
+            let exact = expr.distribution().unwrap();
+            let stats = DiceStats::roll(&mut rng, &expr, 100_000);
+            assert!((stats.mean() - mean(&exact)).abs() < 0.05, "{notation}");
+            for (total, chance) in &exact {
+                assert!(
+                    (stats.frequency(*total) - chance).abs() < 0.01,
+                    "{notation}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut stats = DiceStats::new();
+        assert_eq!(stats.percentile(50.0), None);
+        (1..=100).for_each(|total| stats.add(total));
+        assert_eq!(stats.trials(), 100);
+        assert_close(stats.mean(), 50.5);
+        assert_close(stats.variance(), (100.0 * 100.0 - 1.0) / 12.0);
+        assert_eq!(stats.percentile(0.0), Some(1));
+        assert_eq!(stats.percentile(50.0), Some(50));
+        assert_eq!(stats.percentile(95.0), Some(95));
+        assert_eq!(stats.percentile(100.0), Some(100));
+        assert_close(stats.frequency(7), 0.01);
+        assert_close(stats.frequency(0), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "not within 0..=100")]
+    fn test_percentile_above_100() {
+        DiceStats::new().percentile(101.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "not within 0..=100")]
+    fn test_percentile_nan() {
+        DiceStats::new().percentile(f64::NAN);
+    }
+}
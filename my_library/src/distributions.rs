@@ -0,0 +1,164 @@
+//! Non-uniform distributions, shared by every build of
+//! [`RandomNumberGenerator`](crate::RandomNumberGenerator) and by
+//! [`RandomSource`](crate::RandomSource).
+use rand::Rng;
+use rand_distr::{Distribution, Exp, Normal, Poisson, Triangular};
+use std::ops::Range;
+
+pub(crate) fn gaussian<R: Rng + ?Sized>(rng: &mut R, mean: f32, std_dev: f32) -> f32 {
+    Normal::new(mean, std_dev)
+        .expect("gaussian: std_dev must be finite and not negative")
+        .sample(rng)
+}
+
+pub(crate) fn gaussian_clamped<R: Rng + ?Sized>(
+    rng: &mut R,
+    mean: f32,
+    std_dev: f32,
+    min: f32,
+    max: f32,
+) -> f32 {
+    assert!(min <= max, "gaussian_clamped: min must not exceed max");
+    gaussian(rng, mean, std_dev).clamp(min, max)
+}
+
+pub(crate) fn exponential<R: Rng + ?Sized>(rng: &mut R, mean: f32) -> f32 {
+    assert!(mean > 0.0, "exponential: mean must be positive");
+    Exp::new(1.0 / mean)
+        .expect("exponential: mean must be positive")
+        .sample(rng)
+}
+
+pub(crate) fn poisson<R: Rng + ?Sized>(rng: &mut R, mean: f32) -> u32 {
+    // samples are whole numbers stored in a float
+    Poisson::new(mean)
+        .expect("poisson: mean must be positive and finite")
+        .sample(rng) as u32
+}
+
+pub(crate) fn triangular<R: Rng + ?Sized>(rng: &mut R, min: f32, max: f32, mode: f32) -> f32 {
+    Triangular::new(min, max, mode)
+        .expect("triangular: needs min <= mode <= max")
+        .sample(rng)
+}
+
+pub(crate) fn bell_curve<R: Rng + ?Sized>(rng: &mut R, range: Range<f32>, rolls: u32) -> f32 {
+    assert!(rolls > 0, "bell_curve: needs at least one roll");
+    let sum: f32 = (0..rolls).map(|_| rng.gen_range(range.clone())).sum();
+    sum / rolls as f32
+}
+
+#[cfg(test)]
+// inherent methods take `&mut self` only without the `locking` feature
+#[cfg_attr(feature = "locking", allow(unused_mut))]
+mod test {
+    use crate::{Algorithm, RandomNumberGenerator};
+
+    const SAMPLES: usize = 100_000;
+
+    fn mean_and_variance(samples: &[f32]) -> (f32, f32) {
+        let n = samples.len() as f32;
+        let mean = samples.iter().sum::<f32>() / n;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n;
+        (mean, variance)
+    }
+
+    fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn test_gaussian() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 1);
+            let samples: Vec<f32> = (0..SAMPLES).map(|_| rng.gaussian(10.0, 2.0)).collect();
+            let (mean, variance) = mean_and_variance(&samples);
+            assert_close(mean, 10.0, 0.05);
+            assert_close(variance, 4.0, 0.1);
+            // about 68% fall within one standard deviation
+            let within = samples.iter().filter(|x| (**x - 10.0).abs() < 2.0).count();
+            assert_close(within as f32 / SAMPLES as f32, 0.6827, 0.01);
+        }
+    }
+
+    #[test]
+    fn test_gaussian_clamped() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let samples: Vec<f32> = (0..SAMPLES)
+            .map(|_| rng.gaussian_clamped(0.0, 5.0, -2.0, 2.0))
+            .collect();
+        assert!(samples.iter().all(|x| (-2.0..=2.0).contains(x)));
+        assert!(samples.contains(&-2.0));
+        assert!(samples.contains(&2.0));
+    }
+
+    #[test]
+    fn test_exponential() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 3);
+            let samples: Vec<f32> = (0..SAMPLES).map(|_| rng.exponential(1.5)).collect();
+            assert!(samples.iter().all(|x| *x >= 0.0));
+            let (mean, variance) = mean_and_variance(&samples);
+            assert_close(mean, 1.5, 0.03);
+            assert_close(variance, 2.25, 0.1);
+        }
+    }
+
+    #[test]
+    fn test_poisson() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 4);
+            let samples: Vec<f32> = (0..SAMPLES).map(|_| rng.poisson(4.0) as f32).collect();
+            let (mean, variance) = mean_and_variance(&samples);
+            assert_close(mean, 4.0, 0.05);
+            assert_close(variance, 4.0, 0.1);
+            // P(X = 0) = e^-4
+            let zeros = samples.iter().filter(|x| **x == 0.0).count();
+            assert_close(zeros as f32 / SAMPLES as f32, (-4.0f32).exp(), 0.003);
+        }
+    }
+
+    #[test]
+    fn test_triangular() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 5);
+            let samples: Vec<f32> = (0..SAMPLES)
+                .map(|_| rng.triangular(0.0, 10.0, 2.0))
+                .collect();
+            assert!(samples.iter().all(|x| (0.0..=10.0).contains(x)));
+            let (mean, variance) = mean_and_variance(&samples);
+            // mean (a + b + c) / 3, variance (a² + b² + c² - ab - ac - bc) / 18
+            assert_close(mean, 4.0, 0.05);
+            assert_close(variance, 84.0 / 18.0, 0.1);
+        }
+    }
+
+    #[test]
+    fn test_bell_curve() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 6);
+            let samples: Vec<f32> = (0..SAMPLES).map(|_| rng.bell_curve(0.0..12.0, 3)).collect();
+            assert!(samples.iter().all(|x| (0.0..12.0).contains(x)));
+            let (mean, variance) = mean_and_variance(&samples);
+            // a uniform draw has variance 12² / 12; averaging 3 divides it by 3
+            assert_close(mean, 6.0, 0.05);
+            assert_close(variance, 4.0, 0.1);
+        }
+    }
+
+    #[test]
+    fn test_reproducibility() {
+        let mut rng = (
+            RandomNumberGenerator::seeded(7),
+            RandomNumberGenerator::seeded(7),
+        );
+        (0..1000).for_each(|_| {
+            assert_eq!(rng.0.gaussian(0.0, 1.0), rng.1.gaussian(0.0, 1.0));
+            assert_eq!(rng.0.poisson(3.0), rng.1.poisson(3.0));
+            assert_eq!(rng.0.exponential(2.0), rng.1.exponential(2.0));
+        });
+    }
+}
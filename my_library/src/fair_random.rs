@@ -0,0 +1,320 @@
+//! Randomizers that feel fairer than independent rolls: shuffle bags and
+//! pseudo-random distribution (PRD) chances.
+use crate::RandomSource;
+use bevy::prelude::{Component, Resource};
+
+/// `ShuffleBag` hands out every item it holds once, in random order, before
+/// refilling and reshuffling. Over each cycle every item comes up exactly as
+/// often as it was added, so long streaks (or droughts) can't happen.
+///
+/// It can be stored as a resource or a component.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{RandomNumberGenerator, ShuffleBag};
+/// let mut gaps = ShuffleBag::new().add(-2, 1).add(0, 2).add(2, 1);
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let mut cycle: Vec<i32> = (0..4).map(|_| gaps.draw(&mut rng).unwrap()).collect();
+/// cycle.sort();
+/// assert_eq!(cycle, [-2, 0, 0, 2]);
+/// ```
+#[derive(Clone, Debug, Resource, Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShuffleBag<T> {
+    items: Vec<T>,
+    // what's left of the current cycle; drawn from the back
+    remaining: Vec<T>,
+}
+
+impl<T: Clone> Default for ShuffleBag<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> ShuffleBag<T> {
+    /// Creates an empty bag.
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            remaining: Vec::new(),
+        }
+    }
+
+    /// Adds `copies` of an item to every cycle. Starts a new cycle.
+    pub fn add(mut self, item: T, copies: usize) -> Self {
+        self.items.extend(std::iter::repeat_n(item, copies));
+        self.remaining.clear();
+        self
+    }
+
+    /// Number of items in a full cycle.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// `true` if nothing can be drawn from this bag.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Number of items left before the bag is refilled.
+    pub fn remaining(&self) -> usize {
+        self.remaining.len()
+    }
+
+    /// Throws away the rest of the current cycle.
+    pub fn reset(&mut self) {
+        self.remaining.clear();
+    }
+
+    /// Draws the next item, refilling and reshuffling the bag first if the
+    /// current cycle is used up. Returns `None` if the bag is empty.
+    pub fn draw(&mut self, mut rng: impl RandomSource) -> Option<T> {
+        if self.remaining.is_empty() {
+            self.remaining = self.items.clone();
+            rng.shuffle(&mut self.remaining);
+        }
+        self.remaining.pop()
+    }
+}
+
+/// `PrdChance` is a chance to succeed that grows with every failure and
+/// resets on success (the "pseudo-random distribution" of many games).
+/// It succeeds with the requested probability in the long run, but
+/// streaks of failures and clusters of successes are far rarer than with
+/// independent rolls.
+///
+/// The `n`th attempt after a success succeeds with chance `c * n`, where
+/// `c` is picked so the overall success rate is the requested probability.
+///
+/// It can be stored as a resource or a component.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{PrdChance, RandomNumberGenerator};
+/// let mut critical = PrdChance::new(0.25);
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let hits = (0..1000).filter(|_| critical.roll(&mut rng)).count();
+/// assert!((200..300).contains(&hits));
+/// ```
+#[derive(Clone, Debug, Resource, Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrdChance {
+    probability: f32,
+    increment: f64,
+    failures: u32,
+}
+
+impl PrdChance {
+    /// Creates a chance that succeeds with `probability` in the long run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not within `0.0..=1.0`.
+    pub fn new(probability: f32) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "PrdChance: probability must be within 0.0..=1.0"
+        );
+        Self {
+            probability,
+            increment: prd_increment(f64::from(probability)),
+            failures: 0,
+        }
+    }
+
+    /// The long-run probability of success.
+    pub fn probability(&self) -> f32 {
+        self.probability
+    }
+
+    /// The chance that the next [`roll`](Self::roll) succeeds.
+    pub fn current_chance(&self) -> f32 {
+        (self.increment * (f64::from(self.failures) + 1.0)).min(1.0) as f32
+    }
+
+    /// Forgets past failures, as if the last roll succeeded.
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Rolls for success. Failures raise the chance of the next roll;
+    /// a success resets it.
+    pub fn roll(&mut self, mut rng: impl RandomSource) -> bool {
+        let chance = self.increment * (f64::from(self.failures) + 1.0);
+        if rng.range(0.0..1.0) < chance {
+            self.failures = 0;
+            true
+        } else {
+            // a chance of 0 never succeeds, so keep counting without end
+            self.failures = self.failures.saturating_add(1);
+            false
+        }
+    }
+}
+
+/// Below this probability the increment comes from a closed form instead of
+/// bisection, which would take on the order of `1 / probability` steps.
+const PRD_SMALL_PROBABILITY: f64 = 1e-4;
+
+/// Long-run success rate when the `n`th attempt succeeds with chance
+/// `increment * n`: one over the expected number of attempts per success.
+fn prd_probability(increment: f64) -> f64 {
+    let mut expected_attempts = 0.0;
+    let mut not_yet = 1.0;
+    let mut n = 1.0;
+    // the attempts left once this few streaks remain change nothing an
+    // `f32` probability can show
+    while not_yet >= 1e-12 {
+        let chance = (increment * n).min(1.0);
+        expected_attempts += n * not_yet * chance;
+        not_yet *= 1.0 - chance;
+        n += 1.0;
+    }
+    1.0 / expected_attempts
+}
+
+/// Finds the increment whose long-run success rate is `probability`,
+/// by bisection (the rate grows with the increment).
+fn prd_increment(probability: f64) -> f64 {
+    if probability <= 0.0 {
+        return 0.0;
+    }
+    if probability < PRD_SMALL_PROBABILITY {
+        // streak lengths approach a Rayleigh distribution, whose mean
+        // `√(π / 2C)` gives `C ≈ πp² / 2`; the second factor corrects for
+        // the first attempts, to well within 1e-8 of the exact increment
+        return std::f64::consts::PI / 2.0 * probability.powi(2) * (1.0 - probability * 2.0 / 3.0);
+    }
+    let (mut low, mut high) = (0.0, probability);
+    for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        if prd_probability(mid) < probability {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    high
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(clippy::unnecessary_mut_passed))]
+mod test {
+    use super::*;
+    use crate::RandomNumberGenerator;
+
+    const DRAWS: usize = 100_000;
+
+    #[test]
+    fn test_bag_cycles() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mut bag = ShuffleBag::new().add('a', 1).add('b', 3).add('c', 2);
+        assert_eq!(bag.len(), 6);
+        for _ in 0..1000 {
+            let mut cycle: Vec<char> = (0..6).map(|_| bag.draw(&mut rng).unwrap()).collect();
+            assert_eq!(bag.remaining(), 0);
+            cycle.sort();
+            assert_eq!(cycle, ['a', 'b', 'b', 'b', 'c', 'c']);
+        }
+    }
+
+    #[test]
+    fn test_bag_frequency_and_streaks() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let mut bag = ShuffleBag::new().add(true, 1).add(false, 4);
+        let draws: Vec<bool> = (0..DRAWS).map(|_| bag.draw(&mut rng).unwrap()).collect();
+        assert_eq!(draws.iter().filter(|d| **d).count(), DRAWS / 5);
+        // a miss streak can span at most two cycles
+        let longest = draws
+            .split(|d| *d)
+            .map(|streak| streak.len())
+            .max()
+            .unwrap();
+        assert!(longest <= 8);
+    }
+
+    #[test]
+    fn test_bag_empty_and_reset() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let mut bag: ShuffleBag<u32> = ShuffleBag::new();
+        assert!(bag.is_empty());
+        assert_eq!(bag.draw(&mut rng), None);
+        let mut bag = bag.add(1, 3);
+        bag.draw(&mut rng);
+        assert_eq!(bag.remaining(), 2);
+        bag.reset();
+        assert_eq!(bag.remaining(), 0);
+        bag.draw(&mut rng);
+        assert_eq!(bag.remaining(), 2);
+    }
+
+    #[test]
+    fn test_prd_increment() {
+        // published values for the increment ("C") of Warcraft III's PRD
+        let known = [(0.05, 0.003802), (0.25, 0.08474), (0.5, 0.30210)];
+        for (probability, increment) in known {
+            assert!((prd_increment(probability) - increment).abs() < 1e-5);
+        }
+        assert_eq!(prd_increment(0.0), 0.0);
+        // the closed form for small probabilities meets bisection smoothly
+        let below = prd_increment(PRD_SMALL_PROBABILITY * 0.999_999);
+        let above = prd_increment(PRD_SMALL_PROBABILITY);
+        assert!((below / above - 1.0).abs() < 1e-5);
+        assert!((prd_increment(1.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_prd_frequency() {
+        let mut rng = RandomNumberGenerator::seeded(4);
+        for probability in [0.05, 0.25, 0.5, 0.9] {
+            let mut chance = PrdChance::new(probability);
+            let hits = (0..DRAWS).filter(|_| chance.roll(&mut rng)).count();
+            let frequency = hits as f32 / DRAWS as f32;
+            assert!(
+                (frequency - probability).abs() < 0.01,
+                "{frequency} is not close to {probability}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_prd_streaks() {
+        let mut rng = RandomNumberGenerator::seeded(5);
+        let mut chance = PrdChance::new(0.25);
+        assert!((chance.current_chance() - 0.08474).abs() < 1e-4);
+        let rolls: Vec<bool> = (0..DRAWS).map(|_| chance.roll(&mut rng)).collect();
+        // the chance reaches 1 after ceil(1 / c) - 1 failures
+        let longest = rolls
+            .split(|r| *r)
+            .map(|streak| streak.len())
+            .max()
+            .unwrap();
+        assert!(longest < 12);
+
+        let mut never = PrdChance::new(0.0);
+        assert!((0..1000).all(|_| !never.roll(&mut rng)));
+        let mut always = PrdChance::new(1.0);
+        assert!((0..1000).all(|_| always.roll(&mut rng)));
+
+        // failures stop counting rather than overflow
+        never.failures = u32::MAX - 1;
+        assert!(!never.roll(&mut rng) && !never.roll(&mut rng));
+        assert_eq!(never.failures, u32::MAX);
+        assert_eq!(never.current_chance(), 0.0);
+    }
+
+    #[test]
+    fn test_prd_tiny_probability() {
+        // this would take minutes by bisection
+        let start = std::time::Instant::now();
+        let chance = PrdChance::new(1e-8);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        let expected = std::f64::consts::PI / 2.0 * 1e-16;
+        assert!((f64::from(chance.current_chance()) / expected - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,298 @@
+//! Random points, directions, rotations and colors as Bevy types, shared by
+//! every build of [`RandomNumberGenerator`](crate::RandomNumberGenerator)
+//! and by [`RandomSource`](crate::RandomSource).
+use bevy::{
+    color::Color,
+    math::{Dir2, Dir3, Quat, Rect, Vec2, Vec3},
+};
+use rand::{Rng, distributions::uniform::SampleRange};
+use std::f32::consts::TAU;
+
+pub(crate) fn point_in_rect<R: Rng + ?Sized>(rng: &mut R, rect: Rect) -> Vec2 {
+    // `gen` is in 0..1, so this works for zero-sized rects too
+    rect.min + rect.size() * Vec2::new(rng.r#gen(), rng.r#gen())
+}
+
+pub(crate) fn point_in_circle<R: Rng + ?Sized>(rng: &mut R, center: Vec2, radius: f32) -> Vec2 {
+    // the square root spreads points evenly over the area, not the radius
+    let distance = radius * rng.r#gen::<f32>().sqrt();
+    center + *direction_2d(rng) * distance
+}
+
+pub(crate) fn point_on_circle<R: Rng + ?Sized>(rng: &mut R, center: Vec2, radius: f32) -> Vec2 {
+    center + *direction_2d(rng) * radius
+}
+
+pub(crate) fn direction_2d<R: Rng + ?Sized>(rng: &mut R) -> Dir2 {
+    Dir2::new_unchecked(Vec2::from_angle(rng.gen_range(0.0..TAU)))
+}
+
+pub(crate) fn direction_3d<R: Rng + ?Sized>(rng: &mut R) -> Dir3 {
+    // uniform on the sphere: uniform height, uniform angle around it
+    let z: f32 = rng.gen_range(-1.0..=1.0);
+    let around = Vec2::from_angle(rng.gen_range(0.0..TAU)) * (1.0 - z * z).sqrt();
+    Dir3::new_unchecked(Vec3::new(around.x, around.y, z).normalize())
+}
+
+pub(crate) fn rotation<R: Rng + ?Sized>(rng: &mut R) -> Quat {
+    // Shoemake's uniform random rotation
+    let (u1, u2, u3): (f32, f32, f32) = (rng.r#gen(), rng.r#gen(), rng.r#gen());
+    let (a, b) = ((1.0 - u1).sqrt(), u1.sqrt());
+    Quat::from_xyzw(
+        a * (TAU * u2).sin(),
+        a * (TAU * u2).cos(),
+        b * (TAU * u3).sin(),
+        b * (TAU * u3).cos(),
+    )
+    .normalize()
+}
+
+pub(crate) fn color_hsl<R: Rng + ?Sized>(
+    rng: &mut R,
+    hue: impl SampleRange<f32>,
+    saturation: impl SampleRange<f32>,
+    lightness: impl SampleRange<f32>,
+) -> Color {
+    let hue = rng.gen_range(hue);
+    let saturation = rng.gen_range(saturation);
+    let lightness = rng.gen_range(lightness);
+    Color::hsl(hue, saturation, lightness)
+}
+
+/// Candidates tried around each point before giving up on it.
+const POISSON_ATTEMPTS: usize = 30;
+
+/// The most grid cells, each `min_distance / √2` wide, `poisson_disc` will
+/// cover a rect with: a 2048 by 2048 grid.
+pub(crate) const MAX_POISSON_CELLS: usize = 1 << 22;
+
+// Bridson's algorithm: grow outwards from a random point, trying candidates
+// in the ring between `min_distance` and twice that around an active point.
+pub(crate) fn poisson_disc<R: Rng + ?Sized>(
+    rng: &mut R,
+    rect: Rect,
+    min_distance: f32,
+) -> Vec<Vec2> {
+    assert!(
+        min_distance > 0.0 && min_distance.is_finite(),
+        "poisson_disc: min_distance must be positive and finite"
+    );
+    // also catches NaN
+    if !(rect.width() > 0.0 && rect.height() > 0.0) {
+        return Vec::new();
+    }
+    // a cell is small enough to hold at most one point
+    let cell = min_distance / std::f32::consts::SQRT_2;
+    // at least one cell each way, even if `cell` dwarfs the rect
+    let columns = ((rect.width() / cell).ceil() as usize).max(1);
+    let rows = ((rect.height() / cell).ceil() as usize).max(1);
+    let cells = columns
+        .checked_mul(rows)
+        .filter(|cells| *cells <= MAX_POISSON_CELLS)
+        .unwrap_or_else(|| {
+            panic!(
+                "poisson_disc: a {} by {} rect needs more than {MAX_POISSON_CELLS} cells \
+                 at min_distance {min_distance}",
+                rect.width(),
+                rect.height()
+            )
+        });
+    let mut grid: Vec<Option<usize>> = vec![None; cells];
+    let cell_of = |point: Vec2| {
+        let offset = (point - rect.min) / cell;
+        (
+            (offset.x as usize).min(columns - 1),
+            (offset.y as usize).min(rows - 1),
+        )
+    };
+
+    let first = point_in_rect(rng, rect);
+    let (x, y) = cell_of(first);
+    grid[y * columns + x] = Some(0);
+    let mut points = vec![first];
+    let mut active = vec![0];
+
+    while !active.is_empty() {
+        let index = rng.gen_range(0..active.len());
+        let origin = points[active[index]];
+        let found = (0..POISSON_ATTEMPTS).find_map(|_| {
+            // uniform over the ring's area
+            let squared = rng.gen_range(1.0..4.0f32);
+            let candidate = origin + *direction_2d(rng) * min_distance * squared.sqrt();
+            if !rect.contains(candidate) {
+                return None;
+            }
+            let (x, y) = cell_of(candidate);
+            let near = (y.saturating_sub(2)..(y + 3).min(rows)).any(|ny| {
+                (x.saturating_sub(2)..(x + 3).min(columns)).any(|nx| {
+                    grid[ny * columns + nx]
+                        .is_some_and(|other| points[other].distance(candidate) < min_distance)
+                })
+            });
+            (!near).then_some(candidate)
+        });
+        match found {
+            Some(point) => {
+                let (x, y) = cell_of(point);
+                grid[y * columns + x] = Some(points.len());
+                active.push(points.len());
+                points.push(point);
+            }
+            None => {
+                active.swap_remove(index);
+            }
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+// inherent methods take `&mut self` only without the `locking` feature
+#[cfg_attr(feature = "locking", allow(unused_mut))]
+mod test {
+    use crate::{Algorithm, RandomNumberGenerator};
+    use bevy::{
+        color::{Color, Hsla},
+        math::{Rect, Vec2, Vec3},
+    };
+
+    const SAMPLES: usize = 100_000;
+
+    #[test]
+    fn test_rect() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let rect = Rect::new(-10.0, 5.0, 30.0, 25.0);
+        let mut left = 0;
+        for _ in 0..SAMPLES {
+            let point = rng.point_in_rect(rect);
+            assert!(rect.contains(point));
+            if point.x < 10.0 {
+                left += 1;
+            }
+        }
+        assert!((left as f32 / SAMPLES as f32 - 0.5).abs() < 0.01);
+        let flat = Rect::new(0.0, 1.0, 4.0, 1.0);
+        assert_eq!(rng.point_in_rect(flat).y, 1.0);
+    }
+
+    #[test]
+    fn test_circle() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let center = Vec2::new(3.0, -4.0);
+        let mut inner = 0;
+        for _ in 0..SAMPLES {
+            let distance = rng.point_in_circle(center, 2.0).distance(center);
+            assert!(distance <= 2.0 + 1e-5);
+            if distance < 1.0 {
+                inner += 1;
+            }
+            let edge = rng.point_on_circle(center, 2.0).distance(center);
+            assert!((edge - 2.0).abs() < 1e-5);
+        }
+        // a quarter of the area lies within half the radius
+        assert!((inner as f32 / SAMPLES as f32 - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_directions() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 3);
+            let (mut sum_2d, mut sum_3d) = (Vec2::ZERO, Vec3::ZERO);
+            for _ in 0..SAMPLES {
+                let direction = rng.direction_2d();
+                assert!((direction.length() - 1.0).abs() < 1e-5);
+                sum_2d += *direction;
+                let direction = rng.direction_3d();
+                assert!((direction.length() - 1.0).abs() < 1e-5);
+                sum_3d += *direction;
+            }
+            // no preferred direction
+            assert!((sum_2d / SAMPLES as f32).length() < 0.01);
+            assert!((sum_3d / SAMPLES as f32).length() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_rotation() {
+        let mut rng = RandomNumberGenerator::seeded(4);
+        let mut sum = Vec3::ZERO;
+        for _ in 0..SAMPLES {
+            let rotation = rng.rotation();
+            assert!(rotation.is_normalized());
+            sum += rotation * Vec3::X;
+        }
+        assert!((sum / SAMPLES as f32).length() < 0.01);
+    }
+
+    #[test]
+    fn test_color() {
+        let mut rng = RandomNumberGenerator::seeded(5);
+        for _ in 0..1000 {
+            let color = rng.color_hsl(90.0..150.0, 0.5..=1.0, 0.25..0.75);
+            let Color::Hsla(Hsla {
+                hue,
+                saturation,
+                lightness,
+                ..
+            }) = color
+            else {
+                panic!("expected an HSL color, got {color:?}");
+            };
+            assert!((90.0..150.0).contains(&hue));
+            assert!((0.5..=1.0).contains(&saturation));
+            assert!((0.25..0.75).contains(&lightness));
+        }
+    }
+
+    #[test]
+    fn test_poisson_disc() {
+        let mut rng = RandomNumberGenerator::seeded(6);
+        let rect = Rect::new(0.0, 0.0, 100.0, 60.0);
+        let points = rng.poisson_disc(rect, 5.0);
+        assert!(points.iter().all(|point| rect.contains(*point)));
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                assert!(a.distance(*b) >= 5.0);
+            }
+        }
+        // the rect is filled: nowhere is further than twice the distance
+        // from a point
+        for _ in 0..1000 {
+            let probe = rng.point_in_rect(rect);
+            assert!(points.iter().any(|point| point.distance(probe) < 10.0));
+        }
+        assert!(
+            rng.poisson_disc(Rect::new(0.0, 0.0, 0.0, 10.0), 1.0)
+                .is_empty()
+        );
+        // a distance wider than the rect leaves room for one point
+        assert_eq!(rng.poisson_disc(rect, 1e30).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be positive and finite")]
+    fn test_poisson_disc_infinite_distance() {
+        let mut rng = RandomNumberGenerator::seeded(8);
+        rng.poisson_disc(Rect::new(0.0, 0.0, 10.0, 10.0), f32::INFINITY);
+    }
+
+    #[test]
+    #[should_panic(expected = "cells at min_distance")]
+    fn test_poisson_disc_too_many_cells() {
+        let mut rng = RandomNumberGenerator::seeded(9);
+        rng.poisson_disc(Rect::new(0.0, 0.0, 1e6, 1e6), 1e-3);
+    }
+
+    #[test]
+    fn test_reproducibility() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = (
+                RandomNumberGenerator::with_algorithm(algorithm, 7),
+                RandomNumberGenerator::with_algorithm(algorithm, 7),
+            );
+            let rect = Rect::new(0.0, 0.0, 50.0, 50.0);
+            assert_eq!(rng.0.poisson_disc(rect, 4.0), rng.1.poisson_disc(rect, 4.0));
+            assert_eq!(rng.0.rotation(), rng.1.rotation());
+        }
+    }
+}
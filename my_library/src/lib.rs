@@ -5,21 +5,63 @@
 //!
 //! `my_library` includes:
 //!
-//! * Random number generation facilities.
+//! * Random number generation facilities, with a [`RandomSource`] trait
+//!   shared by every feature combination.
+//! * Gameplay distributions: gaussian, exponential, Poisson, triangular
+//!   and bell curves.
+//! * Shuffling, choosing and sampling from collections and iterators.
+//! * Random points, directions, rotations, colors and Poisson-disc
+//!   scatters as Bevy types.
+//! * Desync detection: an optional draw log that counts and hashes every
+//!   number a generator hands out, with a system that logs it each frame.
+//! * Statistical quality checks for comparing the generator algorithms.
+//! * Dice notation parsing and rolling, with exact and rolled odds (see the
+//!   `dice_stats` binary).
+//! * Weighted random tables (loot tables, spawn tables).
+//! * Shuffle bags and pseudo-random distribution chances, for randomness
+//!   that feels fair.
+//! * Seeded coherent noise (Perlin, Simplex and fBm) for terrain and other
+//!   smoothly varying values.
+//! * Seeded map generators for roguelikes: BSP dungeons, cellular-automata
+//!   caves, drunkard's walk tunnels and mazes.
+//! * Wave function collapse: rule-based tile layouts, with adjacency rules
+//!   learned from an example grid or read from a rules file.
+//! * Markov-chain name generation, trained from word lists loaded as assets
+//!   or from text files.
+//! * An asset manager for images, sounds, fonts and sprite sheets, listed
+//!   in code or in a RON manifest file, and checked up front with a report
+//!   of every missing or broken file.
+//! * A game state plugin with menus and a loading phase that reports
+//!   progress and waits for every asset before showing the menu.
 //!
 //! ## Feature Flags
 //!
-//! The following feature flags are supported: `xorshift`, `pcg`, `locking`.
+//! The following feature flags are supported: `xorshift`, `pcg`, `locking`, `serde`.
 //!
 //! ### Random Number Generation
 //!
 //! * The `locking` feature enables interior mutability inside [`RandomNumberGenerator`],
 //!   allowing it to be used as a resource (`Res<RandomNumberGenerator`)
-//!   rather than requiring mutability (`ResMut<RandomNumberGenerator>`)
+//!   rather than requiring mutability (`ResMut<RandomNumberGenerator>`).
+//!   Draws from the resource itself come from one stream, so systems
+//!   making them at the same time take turns. Systems that run in parallel
+//!   should draw from named streams instead, with
+//!   `RandomNumberGenerator::stream` or a `SystemRng`: each has a lock
+//!   of its own, so they never wait on one another, and each stays
+//!   reproducible from the seed whatever the scheduling. Named streams are
+//!   part of the generator's snapshots and draw log.
 //! * You can control which random number generation algorithm is used by
-//!   specifying *one* of:
+//!   default by specifying *one* of:
 //!   * `xorshift` to use the XorShift algorithm.
-//!   * `pcg` to use the PCG algorithm.
+//!   * `pcg` to use the PCG algorithm (enabled by default).
+//!
+//!   Enabling more than one algorithm is a compile error, so turn off
+//!   default features (`default-features = false`) before picking `xorshift`.
+//!   With neither enabled, ChaCha12 (the algorithm behind the `rand` crate's
+//!   `StdRng`) is used. Every [`Algorithm`] is always available through
+//!   [`RandomNumberGenerator::with_algorithm`]; the features only pick the default.
+//! * The `serde` feature makes [`RandomNumberGenerator`] and [`RngSnapshot`]
+//!   serializable, so generator state can be stored in save games and replays.
 
 /// [`RandomNumberGenerator`] wraps the `rand` crate. The `rand` crate
 /// is re-exported for your convenience.
@@ -31,6 +73,54 @@ pub use bevy_framework::*;
 mod bevy_assets;
 pub use bevy_assets::*;
 
+#[cfg(all(feature = "xorshift", feature = "pcg"))]
+compile_error!(
+    "my_library: the `xorshift` and `pcg` features are mutually exclusive; \
+     enable only one (use `default-features = false` to turn off `pcg`)"
+);
+
+mod seeding;
+
+mod algorithm;
+pub use algorithm::Algorithm;
+
+mod dice;
+pub use dice::*;
+
+mod dice_stats;
+pub use dice_stats::*;
+
+mod random_source;
+pub use random_source::*;
+
+mod desync;
+pub use desync::{DrawLog, log_draw_checksum};
+
+mod distributions;
+mod geometry;
+mod sampling;
+
+mod quality;
+pub use quality::*;
+
+mod random_table;
+pub use random_table::*;
+
+mod fair_random;
+pub use fair_random::*;
+
+mod noise;
+pub use noise::*;
+
+mod mapgen;
+pub use mapgen::*;
+
+mod wfc;
+pub use wfc::*;
+
+mod names;
+pub use names::*;
+
 #[cfg(not(feature = "locking"))]
 mod random;
 
@@ -42,10 +132,8 @@ mod random_locking;
 #[cfg(feature = "locking")]
 pub use random_locking::*;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-}
+mod random_plugin;
+pub use random_plugin::*;
 
 pub mod anyhow {
     pub use anyhow::*;
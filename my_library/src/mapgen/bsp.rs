@@ -0,0 +1,169 @@
+use super::{GridMap, MapGenerator, MapTile};
+use crate::RandomSource;
+use bevy::math::{IRect, IVec2};
+
+/// `BspDungeon` builds rooms and corridors by binary space partitioning:
+/// the map is split in two again and again, a room is dug in each piece,
+/// and the rooms on each side of every split are joined by a corridor.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{BspDungeon, MapGenerator, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let dungeon = BspDungeon::new().min_room(5).generate(&mut rng, 80, 40);
+/// assert!(dungeon.is_connected());
+/// ```
+#[derive(Clone, Debug)]
+pub struct BspDungeon {
+    min_leaf: i32,
+    min_room: i32,
+}
+
+impl Default for BspDungeon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BspDungeon {
+    /// Splits down to pieces at least 10 tiles across, with rooms at least
+    /// 4 tiles across.
+    pub fn new() -> Self {
+        Self {
+            min_leaf: 10,
+            min_room: 4,
+        }
+    }
+
+    /// Sets how small the map may be split. Smaller pieces make more,
+    /// smaller rooms. Always at least one more than the minimum room size.
+    pub fn min_leaf(mut self, min_leaf: i32) -> Self {
+        self.min_leaf = min_leaf.max(self.min_room + 1);
+        self
+    }
+
+    /// Sets the smallest width and height of a room. Raises the minimum
+    /// piece size to fit it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_room` is not positive.
+    pub fn min_room(mut self, min_room: i32) -> Self {
+        assert!(min_room > 0, "BspDungeon: min_room must be positive");
+        self.min_room = min_room;
+        self.min_leaf = self.min_leaf.max(min_room + 1);
+        self
+    }
+
+    /// Digs the rooms of one piece of the map, and returns a floor tile in
+    /// one of them for corridors to aim at.
+    fn dig(&self, rng: &mut impl RandomSource, map: &mut GridMap, area: IRect) -> IVec2 {
+        let size = area.size();
+        let split_x = match (size.x >= 2 * self.min_leaf, size.y >= 2 * self.min_leaf) {
+            (false, false) => return self.dig_room(rng, map, area),
+            (true, false) => true,
+            (false, true) => false,
+            // split across the long side, so pieces don't get too thin
+            (true, true) if size.x * 4 > size.y * 5 => true,
+            (true, true) if size.y * 4 > size.x * 5 => false,
+            (true, true) => rng.range(0..2) == 0,
+        };
+        let (first, second) = if split_x {
+            let at = area.min.x + rng.range(self.min_leaf..=size.x - self.min_leaf);
+            (
+                IRect::new(area.min.x, area.min.y, at, area.max.y),
+                IRect::new(at, area.min.y, area.max.x, area.max.y),
+            )
+        } else {
+            let at = area.min.y + rng.range(self.min_leaf..=size.y - self.min_leaf);
+            (
+                IRect::new(area.min.x, area.min.y, area.max.x, at),
+                IRect::new(area.min.x, at, area.max.x, area.max.y),
+            )
+        };
+        let a = self.dig(rng, map, first);
+        let b = self.dig(rng, map, second);
+        dig_corridor(rng, map, a, b);
+        if rng.range(0..2) == 0 { a } else { b }
+    }
+
+    fn dig_room(&self, rng: &mut impl RandomSource, map: &mut GridMap, area: IRect) -> IVec2 {
+        // leave a wall along the far edges, between this room and the next
+        let space = area.size() - IVec2::ONE;
+        let size = IVec2::new(
+            rng.range(self.min_room.min(space.x)..=space.x),
+            rng.range(self.min_room.min(space.y)..=space.y),
+        );
+        let corner = area.min
+            + IVec2::new(
+                rng.range(0..=space.x - size.x),
+                rng.range(0..=space.y - size.y),
+            );
+        for y in corner.y..corner.y + size.y {
+            for x in corner.x..corner.x + size.x {
+                map.set(IVec2::new(x, y), MapTile::Floor);
+            }
+        }
+        corner + IVec2::new(rng.range(0..size.x), rng.range(0..size.y))
+    }
+}
+
+/// Digs an L-shaped corridor, turning either at `a`'s row or `b`'s.
+fn dig_corridor(rng: &mut impl RandomSource, map: &mut GridMap, a: IVec2, b: IVec2) {
+    let corner = if rng.range(0..2) == 0 {
+        IVec2::new(b.x, a.y)
+    } else {
+        IVec2::new(a.x, b.y)
+    };
+    for (from, to) in [(a, corner), (corner, b)] {
+        let step = (to - from).signum();
+        let mut pos = from;
+        map.set(pos, MapTile::Floor);
+        while pos != to {
+            pos += step;
+            map.set(pos, MapTile::Floor);
+        }
+    }
+}
+
+impl MapGenerator for BspDungeon {
+    /// # Panics
+    ///
+    /// Panics if the map is less than three tiles larger than the minimum
+    /// room size in either direction.
+    fn generate(&self, mut rng: impl RandomSource, width: i32, height: i32) -> GridMap {
+        assert!(
+            width >= self.min_room + 3 && height >= self.min_room + 3,
+            "BspDungeon: a {width}x{height} map can't fit a room"
+        );
+        let mut map = GridMap::new(width, height, MapTile::Wall);
+        // the outermost tiles stay wall
+        self.dig(&mut rng, &mut map, IRect::new(1, 1, width - 1, height - 1));
+        map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mapgen::test::check_generator;
+
+    #[test]
+    fn test_dungeon() {
+        let map = check_generator(&BspDungeon::new(), 80, 50);
+        // mostly rooms, with plenty of rock between them
+        let floor = map.floor_count() as f32 / (80.0 * 50.0);
+        assert!((0.2..0.8).contains(&floor), "{floor}\n{map}");
+    }
+
+    #[test]
+    fn test_settings() {
+        check_generator(&BspDungeon::new().min_leaf(6).min_room(3), 40, 30);
+        // too small to split: one room
+        let map = check_generator(&BspDungeon::new(), 12, 12);
+        assert_eq!(map.regions().len(), 1);
+        let dungeon = BspDungeon::new().min_room(8).min_leaf(2);
+        assert_eq!(dungeon.min_leaf, 9);
+    }
+}
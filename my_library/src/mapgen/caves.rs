@@ -0,0 +1,127 @@
+use super::{GridMap, MapGenerator, MapTile};
+use crate::RandomSource;
+use bevy::math::IVec2;
+
+/// `CellularCaves` grows natural-looking caves with a cellular automaton:
+/// the map starts as random noise, and each step turns tiles surrounded
+/// mostly by rock into rock and the rest into floor. Pockets cut off from
+/// the largest cave are filled in.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{CellularCaves, MapGenerator, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let cave = CellularCaves::new().fill(0.4).steps(4).generate(&mut rng, 80, 40);
+/// assert!(cave.is_connected());
+/// ```
+#[derive(Clone, Debug)]
+pub struct CellularCaves {
+    fill: f32,
+    steps: u32,
+}
+
+impl Default for CellularCaves {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CellularCaves {
+    /// Starts with 45% rock and smooths it five times.
+    pub fn new() -> Self {
+        Self {
+            fill: 0.45,
+            steps: 5,
+        }
+    }
+
+    /// Sets the share of tiles that start as rock. Higher values give
+    /// narrower, more broken caves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fill` is not within `0.0..=1.0`.
+    pub fn fill(mut self, fill: f32) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&fill),
+            "CellularCaves: fill must be within 0.0..=1.0"
+        );
+        self.fill = fill;
+        self
+    }
+
+    /// Sets how many smoothing steps to run. More steps give rounder caves.
+    pub fn steps(mut self, steps: u32) -> Self {
+        self.steps = steps;
+        self
+    }
+}
+
+/// Rock among the eight tiles around `pos`. Off the map counts as rock.
+fn rock_around(map: &GridMap, pos: IVec2) -> usize {
+    (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| IVec2::new(dx, dy)))
+        .filter(|offset| *offset != IVec2::ZERO && !map.is_floor(pos + *offset))
+        .count()
+}
+
+impl MapGenerator for CellularCaves {
+    /// # Panics
+    ///
+    /// Panics if the map is less than 3 tiles in either direction.
+    fn generate(&self, mut rng: impl RandomSource, width: i32, height: i32) -> GridMap {
+        assert!(
+            width >= 3 && height >= 3,
+            "CellularCaves: a {width}x{height} map has no room for a cave"
+        );
+        let mut map = GridMap::new(width, height, MapTile::Wall);
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                if rng.range(0.0..1.0) >= self.fill {
+                    map.set(IVec2::new(x, y), MapTile::Floor);
+                }
+            }
+        }
+
+        for _ in 0..self.steps {
+            let mut next = map.clone();
+            // the outermost tiles stay rock
+            for y in 1..height - 1 {
+                for x in 1..width - 1 {
+                    let pos = IVec2::new(x, y);
+                    match rock_around(&map, pos) {
+                        0..4 => next.set(pos, MapTile::Floor),
+                        4 => {}
+                        _ => next.set(pos, MapTile::Wall),
+                    }
+                }
+            }
+            map = next;
+        }
+        map.keep_largest_region();
+        map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mapgen::test::check_generator;
+
+    #[test]
+    fn test_caves() {
+        let map = check_generator(&CellularCaves::new(), 80, 50);
+        let floor = map.floor_count() as f32 / (80.0 * 50.0);
+        assert!((0.3..0.7).contains(&floor), "{floor}\n{map}");
+    }
+
+    #[test]
+    fn test_fill() {
+        let open = check_generator(&CellularCaves::new().fill(0.3), 60, 40);
+        let tight = check_generator(&CellularCaves::new().fill(0.5), 60, 40);
+        assert!(open.floor_count() > tight.floor_count());
+        // no smoothing leaves the raw noise, cut down to one region
+        check_generator(&CellularCaves::new().steps(0), 60, 40);
+    }
+}
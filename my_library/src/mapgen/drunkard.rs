@@ -0,0 +1,128 @@
+use super::{GridMap, MapGenerator, MapTile, STEPS};
+use crate::RandomSource;
+use bevy::math::IVec2;
+
+/// `DrunkardsWalk` digs winding, organic tunnels: a digger stumbles around
+/// at random, and when it tires a new one sets off from somewhere already
+/// dug, until enough of the map is open.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{DrunkardsWalk, MapGenerator, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let tunnels = DrunkardsWalk::new().coverage(0.3).generate(&mut rng, 80, 40);
+/// assert!(tunnels.floor_count() >= 78 * 38 * 3 / 10);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DrunkardsWalk {
+    coverage: f32,
+    steps: u32,
+}
+
+impl Default for DrunkardsWalk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DrunkardsWalk {
+    /// Opens 40% of the map, with diggers that take 200 steps each.
+    pub fn new() -> Self {
+        Self {
+            coverage: 0.4,
+            steps: 200,
+        }
+    }
+
+    /// Sets the share of the map (inside its outer wall) to open up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `coverage` is not within `0.0..=1.0`.
+    pub fn coverage(mut self, coverage: f32) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&coverage),
+            "DrunkardsWalk: coverage must be within 0.0..=1.0"
+        );
+        self.coverage = coverage;
+        self
+    }
+
+    /// Sets how far each digger wanders. Short walks give blobby, open
+    /// maps; long walks give long tunnels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `steps` is zero.
+    pub fn steps(mut self, steps: u32) -> Self {
+        assert!(steps > 0, "DrunkardsWalk: steps must be positive");
+        self.steps = steps;
+        self
+    }
+}
+
+impl MapGenerator for DrunkardsWalk {
+    /// # Panics
+    ///
+    /// Panics if the map is less than 3 tiles in either direction.
+    fn generate(&self, mut rng: impl RandomSource, width: i32, height: i32) -> GridMap {
+        assert!(
+            width >= 3 && height >= 3,
+            "DrunkardsWalk: a {width}x{height} map has no room to dig"
+        );
+        let mut map = GridMap::new(width, height, MapTile::Wall);
+        let interior = (width - 2) * (height - 2);
+        let target = ((interior as f32 * self.coverage).ceil() as usize).max(1);
+        // the outermost tiles stay wall
+        let (low, high) = (IVec2::ONE, IVec2::new(width - 2, height - 2));
+
+        let mut start = IVec2::new(width / 2, height / 2);
+        map.set(start, MapTile::Floor);
+        let mut dug = 1;
+        while dug < target {
+            let mut pos = start;
+            for _ in 0..self.steps {
+                pos = (pos + *rng.choose(&STEPS).unwrap()).clamp(low, high);
+                if !map.is_floor(pos) {
+                    map.set(pos, MapTile::Floor);
+                    dug += 1;
+                    if dug == target {
+                        break;
+                    }
+                }
+            }
+            // the next digger starts somewhere already open, so it all joins up
+            start = map.random_floor(&mut rng).unwrap();
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{RandomNumberGenerator, mapgen::test::check_generator};
+
+    #[test]
+    fn test_walk() {
+        let map = check_generator(&DrunkardsWalk::new(), 80, 50);
+        assert_eq!(map.floor_count(), (78.0f32 * 48.0 * 0.4).ceil() as usize);
+    }
+
+    #[test]
+    fn test_settings() {
+        check_generator(&DrunkardsWalk::new().coverage(0.6).steps(10), 20, 10);
+        // every seed opens the whole interior, so check those maps directly
+        let map =
+            DrunkardsWalk::new()
+                .coverage(1.0)
+                .generate(RandomNumberGenerator::seeded(1), 20, 10);
+        assert_eq!(map.floor_count(), 18 * 8);
+        let map =
+            DrunkardsWalk::new()
+                .coverage(0.0)
+                .generate(RandomNumberGenerator::seeded(1), 20, 10);
+        assert_eq!(map.floor_tiles().collect::<Vec<_>>(), [IVec2::new(10, 5)]);
+    }
+}
@@ -0,0 +1,87 @@
+use super::{GridMap, MapGenerator, MapTile, STEPS};
+use crate::RandomSource;
+use bevy::math::IVec2;
+
+/// `Maze` carves a perfect maze (exactly one path between any two points)
+/// with a randomized depth-first search. Passages are one tile wide and run
+/// along odd rows and columns; with an even width or height the last row
+/// or column stays solid.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{MapGenerator, Maze, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let maze = Maze.generate(&mut rng, 21, 11);
+/// assert!(maze.is_connected());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Maze;
+
+impl MapGenerator for Maze {
+    /// # Panics
+    ///
+    /// Panics if the map is less than 3 tiles in either direction.
+    fn generate(&self, mut rng: impl RandomSource, width: i32, height: i32) -> GridMap {
+        assert!(
+            width >= 3 && height >= 3,
+            "Maze: a {width}x{height} map has no room for a passage"
+        );
+        let mut map = GridMap::new(width, height, MapTile::Wall);
+        // cells sit on odd coordinates, with walls between them
+        let is_cell = |pos: IVec2| {
+            pos.x % 2 == 1 && pos.y % 2 == 1 && pos.x < width - 1 && pos.y < height - 1
+        };
+        let columns = (width - 1) / 2;
+        let rows = (height - 1) / 2;
+        let start = IVec2::new(rng.range(0..columns), rng.range(0..rows)) * 2 + IVec2::ONE;
+        map.set(start, MapTile::Floor);
+
+        let mut path = vec![start];
+        while let Some(&cell) = path.last() {
+            let unvisited: Vec<IVec2> = STEPS
+                .iter()
+                .map(|step| cell + *step * 2)
+                .filter(|next| is_cell(*next) && !map.is_floor(*next))
+                .collect();
+            match rng.choose(&unvisited) {
+                Some(&next) => {
+                    map.set((cell + next) / 2, MapTile::Floor);
+                    map.set(next, MapTile::Floor);
+                    path.push(next);
+                }
+                None => {
+                    path.pop();
+                }
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mapgen::test::check_generator;
+
+    #[test]
+    fn test_maze() {
+        let map = check_generator(&Maze, 41, 21);
+        // every cell is open, and a perfect maze on n cells has n - 1
+        // passages between them
+        let cells = 20 * 10;
+        assert_eq!(map.floor_count(), cells + cells - 1);
+        for y in (1..21).step_by(2) {
+            for x in (1..41).step_by(2) {
+                assert!(map.is_floor(IVec2::new(x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_even_size() {
+        let map = check_generator(&Maze, 12, 8);
+        assert!((0..8).all(|y| !map.is_floor(IVec2::new(10, y))));
+        assert_eq!(map.floor_count(), 5 * 3 * 2 - 1);
+    }
+}
@@ -0,0 +1,311 @@
+//! Procedural maps for roguelikes: a grid of walls and floors, and
+//! generators for dungeons, caves and mazes. Every generator draws from a
+//! [`RandomSource`], so the same seed always gives the same map.
+use crate::RandomSource;
+use bevy::{
+    math::IVec2,
+    prelude::{Component, Resource},
+};
+use std::{collections::VecDeque, fmt};
+
+mod bsp;
+pub use bsp::BspDungeon;
+
+mod caves;
+pub use caves::CellularCaves;
+
+mod drunkard;
+pub use drunkard::DrunkardsWalk;
+
+mod maze;
+pub use maze::Maze;
+
+/// The four orthogonal steps. Maps are connected through these only, never
+/// diagonally.
+const STEPS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+/// One cell of a [`GridMap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MapTile {
+    /// Solid rock: blocks movement.
+    Wall,
+    /// Open ground.
+    Floor,
+}
+
+/// Something that builds a [`GridMap`]. Generators only hold settings, so
+/// one can build any number of maps.
+pub trait MapGenerator {
+    /// Builds a `width` by `height` map. The outermost tiles are always
+    /// walls, and every floor tile can be reached from every other.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map is too small for the generator's settings.
+    fn generate(&self, rng: impl RandomSource, width: i32, height: i32) -> GridMap;
+}
+
+/// `GridMap` is a rectangle of [`MapTile`]s, addressed by `IVec2` positions
+/// from `(0, 0)` to `(width - 1, height - 1)`.
+///
+/// It can be stored as a resource or a component.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{CellularCaves, MapGenerator, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let cave = CellularCaves::new().generate(&mut rng, 60, 30);
+/// assert!(cave.is_connected());
+/// let spawn = cave.random_floor(&mut rng).unwrap();
+/// println!("{cave}");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Resource, Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridMap {
+    width: i32,
+    height: i32,
+    tiles: Vec<MapTile>,
+}
+
+impl GridMap {
+    /// Creates a map with every tile set to `fill`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is not positive.
+    pub fn new(width: i32, height: i32, fill: MapTile) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "GridMap: width and height must be positive"
+        );
+        Self {
+            width,
+            height,
+            tiles: vec![fill; (width * height) as usize],
+        }
+    }
+
+    /// Number of columns.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// `true` if `pos` is on the map.
+    pub fn in_bounds(&self, pos: IVec2) -> bool {
+        (0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y)
+    }
+
+    fn index(&self, pos: IVec2) -> usize {
+        (pos.y * self.width + pos.x) as usize
+    }
+
+    /// The tile at `pos`, or `None` off the map.
+    pub fn tile(&self, pos: IVec2) -> Option<MapTile> {
+        self.in_bounds(pos).then(|| self.tiles[self.index(pos)])
+    }
+
+    /// Changes the tile at `pos`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is off the map.
+    pub fn set(&mut self, pos: IVec2, tile: MapTile) {
+        assert!(self.in_bounds(pos), "GridMap: {pos} is off the map");
+        let index = self.index(pos);
+        self.tiles[index] = tile;
+    }
+
+    /// `true` if `pos` is a floor tile. Everything off the map is wall.
+    pub fn is_floor(&self, pos: IVec2) -> bool {
+        self.tile(pos) == Some(MapTile::Floor)
+    }
+
+    /// Every floor position, row by row.
+    pub fn floor_tiles(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| IVec2::new(x, y)))
+            .filter(|pos| self.is_floor(*pos))
+    }
+
+    /// Number of floor tiles.
+    pub fn floor_count(&self) -> usize {
+        self.tiles.iter().filter(|t| **t == MapTile::Floor).count()
+    }
+
+    /// A random floor position, or `None` if there is no floor.
+    pub fn random_floor(&self, mut rng: impl RandomSource) -> Option<IVec2> {
+        rng.choose_iter(self.floor_tiles())
+    }
+
+    /// Every floor position reachable from `start` (including `start`),
+    /// nearest first. Empty if `start` is not floor.
+    pub fn flood_fill(&self, start: IVec2) -> Vec<IVec2> {
+        let mut seen = vec![false; self.tiles.len()];
+        self.fill_from(start, &mut seen)
+    }
+
+    fn fill_from(&self, start: IVec2, seen: &mut [bool]) -> Vec<IVec2> {
+        if !self.is_floor(start) || seen[self.index(start)] {
+            return Vec::new();
+        }
+        seen[self.index(start)] = true;
+        let mut reached = Vec::new();
+        let mut open = VecDeque::from([start]);
+        while let Some(pos) = open.pop_front() {
+            reached.push(pos);
+            for next in STEPS.map(|step| pos + step) {
+                if self.is_floor(next) && !seen[self.index(next)] {
+                    seen[self.index(next)] = true;
+                    open.push_back(next);
+                }
+            }
+        }
+        reached
+    }
+
+    /// The groups of floor tiles that are connected to each other but not
+    /// to any other group, largest first.
+    pub fn regions(&self) -> Vec<Vec<IVec2>> {
+        let mut seen = vec![false; self.tiles.len()];
+        let mut regions: Vec<Vec<IVec2>> = self
+            .floor_tiles()
+            .map(|pos| self.fill_from(pos, &mut seen))
+            .filter(|region| !region.is_empty())
+            .collect();
+        regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+        regions
+    }
+
+    /// `true` if every floor tile can be reached from every other (or there
+    /// is at most one floor tile).
+    pub fn is_connected(&self) -> bool {
+        match self.floor_tiles().next() {
+            Some(start) => self.flood_fill(start).len() == self.floor_count(),
+            None => true,
+        }
+    }
+
+    /// Fills every region but the largest with wall, so the map is connected.
+    pub fn keep_largest_region(&mut self) {
+        for region in self.regions().iter().skip(1) {
+            region.iter().for_each(|pos| self.set(*pos, MapTile::Wall));
+        }
+    }
+}
+
+/// Draws the map with `#` for walls and `.` for floors, top row first.
+impl fmt::Display for GridMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.tiles.chunks(self.width as usize) {
+            for tile in row {
+                let glyph = match tile {
+                    MapTile::Wall => '#',
+                    MapTile::Floor => '.',
+                };
+                write!(f, "{glyph}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(clippy::unnecessary_mut_passed))]
+pub(crate) mod test {
+    use super::*;
+    use crate::RandomNumberGenerator;
+
+    /// Checks what every generator promises, and returns the map.
+    pub(crate) fn check_generator(
+        generator: &impl MapGenerator,
+        width: i32,
+        height: i32,
+    ) -> GridMap {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let map = generator.generate(&mut rng, width, height);
+        assert_eq!((map.width(), map.height()), (width, height));
+        assert!(map.floor_count() > 0);
+        assert!(map.is_connected(), "not connected:\n{map}");
+        for x in 0..width {
+            assert!(!map.is_floor(IVec2::new(x, 0)) && !map.is_floor(IVec2::new(x, height - 1)));
+        }
+        for y in 0..height {
+            assert!(!map.is_floor(IVec2::new(0, y)) && !map.is_floor(IVec2::new(width - 1, y)));
+        }
+
+        // the same seed gives the same map; another seed another map
+        let again = generator.generate(RandomNumberGenerator::seeded(1), width, height);
+        assert_eq!(map, again);
+        let other = generator.generate(RandomNumberGenerator::seeded(2), width, height);
+        assert_ne!(map, other);
+        map
+    }
+
+    fn parse(rows: &[&str]) -> GridMap {
+        let mut map = GridMap::new(rows[0].len() as i32, rows.len() as i32, MapTile::Wall);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, glyph) in row.chars().enumerate() {
+                if glyph == '.' {
+                    map.set(IVec2::new(x as i32, y as i32), MapTile::Floor);
+                }
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_tiles() {
+        let mut map = GridMap::new(4, 3, MapTile::Wall);
+        assert_eq!(map.tile(IVec2::new(3, 2)), Some(MapTile::Wall));
+        assert_eq!(map.tile(IVec2::new(4, 0)), None);
+        assert_eq!(map.tile(IVec2::new(0, -1)), None);
+        map.set(IVec2::new(1, 1), MapTile::Floor);
+        assert!(map.is_floor(IVec2::new(1, 1)));
+        assert!(!map.is_floor(IVec2::new(-1, 1)));
+        assert_eq!(map.floor_tiles().collect::<Vec<_>>(), [IVec2::new(1, 1)]);
+        assert_eq!(map.to_string(), "####\n#.##\n####\n");
+    }
+
+    #[test]
+    fn test_regions() {
+        let mut map = parse(&[
+            "#######", //
+            "#..#..#", //
+            "#..#.##", //
+            "####..#", //
+            "#.#####", //
+        ]);
+        assert!(!map.is_connected());
+        let sizes: Vec<usize> = map.regions().iter().map(Vec::len).collect();
+        assert_eq!(sizes, [5, 4, 1]);
+        assert_eq!(map.flood_fill(IVec2::new(1, 1)).len(), 4);
+        // diagonal neighbors don't connect
+        assert_eq!(map.flood_fill(IVec2::new(1, 4)).len(), 1);
+        assert!(map.flood_fill(IVec2::new(0, 0)).is_empty());
+
+        map.keep_largest_region();
+        assert!(map.is_connected());
+        assert_eq!(map.floor_count(), 5);
+        assert!(GridMap::new(3, 3, MapTile::Wall).is_connected());
+    }
+
+    #[test]
+    fn test_random_floor() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let map = parse(&["###", "#.#", "###"]);
+        assert_eq!(map.random_floor(&mut rng), Some(IVec2::new(1, 1)));
+        assert_eq!(
+            GridMap::new(3, 3, MapTile::Wall).random_floor(&mut rng),
+            None
+        );
+    }
+}
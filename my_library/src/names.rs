@@ -0,0 +1,318 @@
+//! Markov-chain name generation: learn which letters follow which from a
+//! list of example words, then string together new words that sound like
+//! them.
+use crate::RandomSource;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use std::{collections::HashMap, io, path::Path};
+
+/// Pads the start of every word and marks its end. Never part of a name.
+const BOUNDARY: char = '\0';
+
+/// How many names [`NameGenerator::generate`] tries before giving up.
+const MAX_ATTEMPTS: usize = 1000;
+
+/// `NameGenerator` is an order-N character Markov model. It learns which
+/// letter follows each run of N letters in its training words, and builds
+/// new names one letter at a time from those odds. Low orders give wilder
+/// names; high orders stay close to the training words.
+///
+/// Training is case-insensitive, and generated names are capitalized.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{NameGenerator, RandomNumberGenerator};
+/// let dragons = NameGenerator::new(2)
+///     .train(["smaug", "glaurung", "ancalagon", "scatha", "fafnir", "nidhogg"])
+///     .min_length(4)
+///     .max_length(9)
+///     .ban("gg");
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let name = dragons.generate(&mut rng).unwrap();
+/// assert!((4..=9).contains(&name.chars().count()));
+/// assert!(!name.contains("gg"));
+/// ```
+#[derive(Clone, Debug, Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NameGenerator {
+    order: usize,
+    // the last `order` letters (padded at the start) -> the letters that
+    // followed them, and how often
+    transitions: HashMap<String, Vec<(char, u32)>>,
+    min_length: usize,
+    max_length: usize,
+    banned: Vec<String>,
+}
+
+impl NameGenerator {
+    /// Creates an untrained model that looks at the last `order` letters,
+    /// making names of 3 to 12 letters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is zero.
+    pub fn new(order: usize) -> Self {
+        assert!(order > 0, "NameGenerator: order must be at least 1");
+        Self {
+            order,
+            transitions: HashMap::new(),
+            min_length: 3,
+            max_length: 12,
+            banned: Vec::new(),
+        }
+    }
+
+    /// Learns from more example words. Blank words are skipped.
+    pub fn train<S: AsRef<str>>(mut self, words: impl IntoIterator<Item = S>) -> Self {
+        for word in words {
+            let word = word.as_ref().trim().to_lowercase();
+            if word.is_empty() {
+                continue;
+            }
+            let letters: Vec<char> = std::iter::repeat_n(BOUNDARY, self.order)
+                .chain(word.chars())
+                .chain([BOUNDARY])
+                .collect();
+            for window in letters.windows(self.order + 1) {
+                let (context, next) = window.split_at(self.order);
+                let followers = self
+                    .transitions
+                    .entry(context.iter().collect())
+                    .or_default();
+                match followers.iter_mut().find(|(letter, _)| *letter == next[0]) {
+                    Some((_, count)) => *count += 1,
+                    None => followers.push((next[0], 1)),
+                }
+            }
+        }
+        self
+    }
+
+    /// Sets the shortest name to produce, in letters.
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Sets the longest name to produce, in letters.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Never produces names containing `substring` (ignoring case).
+    pub fn ban(mut self, substring: impl AsRef<str>) -> Self {
+        self.banned.push(substring.as_ref().to_lowercase());
+        self
+    }
+
+    /// `true` if the model has learned from at least one word.
+    pub fn is_trained(&self) -> bool {
+        !self.transitions.is_empty()
+    }
+
+    /// Makes a new name. Returns `None` if the model is untrained, or no
+    /// name that passes the length and banned-substring filters turned up
+    /// after many attempts.
+    pub fn generate(&self, mut rng: impl RandomSource) -> Option<String> {
+        if !self.is_trained() {
+            return None;
+        }
+        (0..MAX_ATTEMPTS).find_map(|_| {
+            let name = self.attempt(&mut rng)?;
+            let length = name.chars().count();
+            let allowed = (self.min_length..=self.max_length).contains(&length)
+                && !self.banned.iter().any(|banned| name.contains(banned));
+            allowed.then(|| capitalize(&name))
+        })
+    }
+
+    /// Walks the chain once. `None` if the name grows too long.
+    fn attempt(&self, rng: &mut impl RandomSource) -> Option<String> {
+        let mut context: Vec<char> = vec![BOUNDARY; self.order];
+        let mut name = String::new();
+        loop {
+            let key: String = context[context.len() - self.order..].iter().collect();
+            // every context reached was seen in training, so has followers
+            let (next, _) =
+                rng.choose_weighted(&self.transitions[&key], |(_, count)| *count as f32)?;
+            if *next == BOUNDARY {
+                return Some(name);
+            }
+            if name.chars().count() == self.max_length {
+                return None;
+            }
+            name.push(*next);
+            context.push(*next);
+        }
+    }
+}
+
+fn capitalize(name: &str) -> String {
+    let mut letters = name.chars();
+    match letters.next() {
+        Some(first) => first.to_uppercase().chain(letters).collect(),
+        None => String::new(),
+    }
+}
+
+/// `WordList` is training data for a [`NameGenerator`]: one word per line.
+/// Blank lines and lines starting with `#` are skipped.
+///
+/// Load it from a plain text file with [`WordList::from_file`], or through
+/// Bevy's asset system by registering a `.names` file with
+/// [`AssetManager::add_word_list`](crate::AssetManager::add_word_list).
+#[derive(Asset, TypePath, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WordList {
+    words: Vec<String>,
+}
+
+impl WordList {
+    /// Reads words from text, one per line.
+    pub fn parse(text: &str) -> Self {
+        Self {
+            words: text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Reads words from a plain text file, one per line.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// The words, in file order.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+}
+
+/// Loads `.names` files as [`WordList`] assets.
+#[derive(Default)]
+pub struct WordListLoader;
+
+impl AssetLoader for WordListLoader {
+    type Asset = WordList;
+    type Settings = ();
+    type Error = io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<WordList, io::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8(bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(WordList::parse(&text))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["names"]
+    }
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(clippy::unnecessary_mut_passed))]
+mod test {
+    use super::*;
+    use crate::RandomNumberGenerator;
+
+    const ELVES: [&str; 12] = [
+        "aerandir",
+        "celeborn",
+        "elrond",
+        "galadriel",
+        "gildor",
+        "glorfindel",
+        "haldir",
+        "legolas",
+        "lindir",
+        "thranduil",
+        "arwen",
+        "earendil",
+    ];
+
+    #[test]
+    fn test_filters() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let names = NameGenerator::new(2)
+            .train(ELVES)
+            .min_length(5)
+            .max_length(8)
+            .ban("dir");
+        for _ in 0..200 {
+            let name = names.generate(&mut rng).unwrap();
+            assert!((5..=8).contains(&name.chars().count()), "{name}");
+            assert!(!name.to_lowercase().contains("dir"), "{name}");
+            assert!(name.starts_with(char::is_uppercase), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        // every run of 9 letters is unique, so only the training words come out
+        let copycat = NameGenerator::new(9).train(ELVES).max_length(20);
+        for _ in 0..50 {
+            let name = copycat.generate(&mut rng).unwrap().to_lowercase();
+            assert!(ELVES.contains(&name.as_str()), "{name}");
+        }
+        // a low order invents new names
+        let inventive = NameGenerator::new(1).train(ELVES);
+        assert!((0..50).any(|_| {
+            let name = inventive.generate(&mut rng).unwrap().to_lowercase();
+            !ELVES.contains(&name.as_str())
+        }));
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let names = NameGenerator::new(3).train(ELVES);
+        let mut rng = (
+            RandomNumberGenerator::seeded(3),
+            RandomNumberGenerator::seeded(3),
+        );
+        for _ in 0..20 {
+            assert_eq!(names.generate(&mut rng.0), names.generate(&mut rng.1));
+        }
+    }
+
+    #[test]
+    fn test_impossible() {
+        let mut rng = RandomNumberGenerator::seeded(4);
+        assert!(!NameGenerator::new(2).is_trained());
+        assert_eq!(NameGenerator::new(2).generate(&mut rng), None);
+        let too_long = NameGenerator::new(2).train(["ab", "abc"]).min_length(10);
+        assert_eq!(too_long.generate(&mut rng), None);
+        let banned = NameGenerator::new(2).train(["ab"]).ban("A");
+        assert_eq!(banned.generate(&mut rng), None);
+    }
+
+    #[test]
+    fn test_word_list() {
+        let list = WordList::parse("# dragons\nSmaug\n\n  Glaurung \n#Nidhogg\n");
+        assert_eq!(list.words(), ["Smaug", "Glaurung"]);
+
+        let path = std::env::temp_dir().join("my_library_test_word_list.names");
+        std::fs::write(&path, "smaug\nfafnir\n").unwrap();
+        let list = WordList::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(list.words(), ["smaug", "fafnir"]);
+        assert!(WordList::from_file(&path).is_err());
+
+        let mut rng = RandomNumberGenerator::seeded(5);
+        let names = NameGenerator::new(2).train(list.words());
+        assert!(names.generate(&mut rng).is_some());
+    }
+}
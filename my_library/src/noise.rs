@@ -0,0 +1,548 @@
+//! Seeded coherent noise: Perlin and Simplex noise in one, two and three
+//! dimensions, and fractal Brownian motion (fBm) layered on top of either.
+//!
+//! Unlike independent random numbers, coherent noise changes smoothly:
+//! nearby inputs give nearby outputs. That makes it a good fit for terrain,
+//! clouds, and anything else that should vary gradually.
+use crate::RandomSource;
+
+/// Coherent noise that can be sampled in one, two or three dimensions.
+/// Every sample is within `-1.0..=1.0`, and the same input always gives
+/// the same output. The noises here sample coordinates that aren't finite
+/// (NaN or infinite) as `0.0`.
+pub trait Noise {
+    /// Samples the noise along a line.
+    fn sample_1d(&self, x: f32) -> f32;
+
+    /// Samples the noise on a plane.
+    fn sample_2d(&self, x: f32, y: f32) -> f32;
+
+    /// Samples the noise in space.
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32;
+}
+
+/// Brings a raw sample into `-1.0..=1.0`, and a NaN one (from a coordinate
+/// that isn't finite) to `0.0`, since `clamp` keeps NaN as it is.
+fn bounded(value: f32) -> f32 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(-1.0, 1.0)
+    }
+}
+
+/// A shuffled table of lattice hashes, repeated twice so lookups
+/// can add offsets without wrapping. Lattice coordinates only matter modulo
+/// 256, so callers step to neighbors with `wrapping_add`: far-off inputs
+/// (where `as i32` saturates) can't overflow.
+#[derive(Clone, Debug)]
+struct Permutation(Vec<u8>);
+
+impl Permutation {
+    fn new(mut rng: impl RandomSource) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        rng.shuffle(&mut table);
+        table.extend_from_within(..);
+        Self(table)
+    }
+
+    fn hash(&self, x: i32) -> usize {
+        usize::from(self.0[(x & 255) as usize])
+    }
+
+    fn hash2(&self, x: i32, y: i32) -> usize {
+        usize::from(self.0[self.hash(x) + (y & 255) as usize])
+    }
+
+    fn hash3(&self, x: i32, y: i32, z: i32) -> usize {
+        usize::from(self.0[self.hash2(x, y) + (z & 255) as usize])
+    }
+}
+
+/// Gradient in `-8..=8` (never zero), dotted with the offset.
+fn grad1(hash: usize, x: f32) -> f32 {
+    let gradient = 1.0 + (hash & 7) as f32;
+    if hash & 8 == 0 {
+        gradient * x
+    } else {
+        -gradient * x
+    }
+}
+
+/// One of eight directions, dotted with the offset.
+fn grad2(hash: usize, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+/// One of the twelve cube edge directions, dotted with the offset.
+fn grad3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// `Perlin` is Ken Perlin's "improved" gradient noise. It is zero at every
+/// whole-number coordinate and varies smoothly in between.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{Noise, Perlin, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let terrain = Perlin::new(&mut rng.fork("terrain"));
+/// let height = terrain.sample_2d(3.5, 7.25);
+/// assert!((-1.0..=1.0).contains(&height));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Perlin {
+    permutation: Permutation,
+}
+
+impl Perlin {
+    /// Creates noise seeded from `rng`. Pass a
+    /// [`fork`](crate::RandomNumberGenerator::fork) of your generator so
+    /// the noise doesn't depend on what else has been drawn from it.
+    pub fn new(rng: impl RandomSource) -> Self {
+        Self {
+            permutation: Permutation::new(rng),
+        }
+    }
+}
+
+impl Noise for Perlin {
+    fn sample_1d(&self, x: f32) -> f32 {
+        let x0 = x.floor();
+        let (i, x) = (x0 as i32, x - x0);
+        let p = &self.permutation;
+        let value = lerp(
+            fade(x),
+            grad1(p.hash(i), x),
+            grad1(p.hash(i.wrapping_add(1)), x - 1.0),
+        );
+        // |value| peaks at 8 * 0.5, halfway between lattice points
+        bounded(value * 0.25)
+    }
+
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (i, j) = (x0 as i32, y0 as i32);
+        let (x, y) = (x - x0, y - y0);
+        let (u, v) = (fade(x), fade(y));
+        let p = &self.permutation;
+        let value = lerp(
+            v,
+            lerp(
+                u,
+                grad2(p.hash2(i, j), x, y),
+                grad2(p.hash2(i.wrapping_add(1), j), x - 1.0, y),
+            ),
+            lerp(
+                u,
+                grad2(p.hash2(i, j.wrapping_add(1)), x, y - 1.0),
+                grad2(
+                    p.hash2(i.wrapping_add(1), j.wrapping_add(1)),
+                    x - 1.0,
+                    y - 1.0,
+                ),
+            ),
+        );
+        bounded(value)
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (i, j, k) = (x0 as i32, y0 as i32, z0 as i32);
+        let (x, y, z) = (x - x0, y - y0, z - z0);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        let p = &self.permutation;
+        let corner = |di: i32, dj: i32, dk: i32| {
+            grad3(
+                p.hash3(i.wrapping_add(di), j.wrapping_add(dj), k.wrapping_add(dk)),
+                x - di as f32,
+                y - dj as f32,
+                z - dk as f32,
+            )
+        };
+        let value = lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+                lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+            ),
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+                lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+            ),
+        );
+        bounded(value)
+    }
+}
+
+/// `Simplex` is Ken Perlin's simplex noise. It looks much like [`Perlin`]
+/// noise, with fewer grid-aligned artifacts, and is cheaper to sample in
+/// higher dimensions.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{Noise, RandomNumberGenerator, Simplex};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let clouds = Simplex::new(&mut rng.fork("clouds"));
+/// let density = clouds.sample_3d(1.0, 2.0, 0.5);
+/// assert!((-1.0..=1.0).contains(&density));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Simplex {
+    permutation: Permutation,
+}
+
+impl Simplex {
+    /// Creates noise seeded from `rng`. Pass a
+    /// [`fork`](crate::RandomNumberGenerator::fork) of your generator so
+    /// the noise doesn't depend on what else has been drawn from it.
+    pub fn new(rng: impl RandomSource) -> Self {
+        Self {
+            permutation: Permutation::new(rng),
+        }
+    }
+}
+
+// Skewing factors between the simplex grid and the square grid.
+const F2: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+const G2: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+const F3: f32 = 1.0 / 3.0;
+const G3: f32 = 1.0 / 6.0;
+
+impl Noise for Simplex {
+    fn sample_1d(&self, x: f32) -> f32 {
+        let x0 = x.floor();
+        let (i, x) = (x0 as i32, x - x0);
+        let p = &self.permutation;
+        let corner = |hash: usize, x: f32| {
+            let t = 1.0 - x * x;
+            t * t * t * t * grad1(hash, x)
+        };
+        let value = corner(p.hash(i), x) + corner(p.hash(i.wrapping_add(1)), x - 1.0);
+        bounded(value * 0.395)
+    }
+
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor() as i32, (y + s).floor() as i32);
+        let t = (i as f32 + j as f32) * G2;
+        let (x0, y0) = (x - (i as f32 - t), y - (j as f32 - t));
+        // which of the two triangles in the skewed square we're in
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let p = &self.permutation;
+        let corner = |hash: usize, x: f32, y: f32| {
+            let t = 0.5 - x * x - y * y;
+            if t < 0.0 {
+                0.0
+            } else {
+                t * t * t * t * grad2(hash, x, y)
+            }
+        };
+        let value = corner(p.hash2(i, j), x0, y0)
+            + corner(
+                p.hash2(i.wrapping_add(i1), j.wrapping_add(j1)),
+                x0 - i1 as f32 + G2,
+                y0 - j1 as f32 + G2,
+            )
+            + corner(
+                p.hash2(i.wrapping_add(1), j.wrapping_add(1)),
+                x0 - 1.0 + 2.0 * G2,
+                y0 - 1.0 + 2.0 * G2,
+            );
+        bounded(value * 70.0)
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let s = (x + y + z) * F3;
+        let (i, j, k) = (
+            (x + s).floor() as i32,
+            (y + s).floor() as i32,
+            (z + s).floor() as i32,
+        );
+        let t = (i as f32 + j as f32 + k as f32) * G3;
+        let (x0, y0, z0) = (x - (i as f32 - t), y - (j as f32 - t), z - (k as f32 - t));
+        // which of the six tetrahedra in the skewed cube we're in
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+        let p = &self.permutation;
+        let corner = |(di, dj, dk): (i32, i32, i32), offset: f32| {
+            let (x, y, z) = (
+                x0 - di as f32 + offset,
+                y0 - dj as f32 + offset,
+                z0 - dk as f32 + offset,
+            );
+            let t = 0.6 - x * x - y * y - z * z;
+            if t < 0.0 {
+                0.0
+            } else {
+                t * t
+                    * t
+                    * t
+                    * grad3(
+                        p.hash3(i.wrapping_add(di), j.wrapping_add(dj), k.wrapping_add(dk)),
+                        x,
+                        y,
+                        z,
+                    )
+            }
+        };
+        let value = corner((0, 0, 0), 0.0)
+            + corner((i1, j1, k1), G3)
+            + corner((i2, j2, k2), 2.0 * G3)
+            + corner((1, 1, 1), 3.0 * G3);
+        bounded(value * 32.0)
+    }
+}
+
+/// `Fbm` (fractal Brownian motion) adds up several octaves of another
+/// noise, each at a higher frequency and lower amplitude than the last.
+/// The result has large features with finer detail on top, like terrain.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{Fbm, Noise, Perlin, RandomNumberGenerator};
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let terrain = Fbm::new(Perlin::new(&mut rng.fork("terrain")))
+///     .octaves(5)
+///     .gain(0.5);
+/// let height = terrain.sample_2d(0.3, 0.7);
+/// assert!((-1.0..=1.0).contains(&height));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Fbm<N> {
+    noise: N,
+    octaves: u32,
+    lacunarity: f32,
+    gain: f32,
+}
+
+impl<N: Noise> Fbm<N> {
+    /// Layers `noise` with four octaves, each at twice the frequency and
+    /// half the amplitude of the last.
+    pub fn new(noise: N) -> Self {
+        Self {
+            noise,
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    /// Sets the number of octaves to add up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `octaves` is zero.
+    pub fn octaves(mut self, octaves: u32) -> Self {
+        assert!(octaves > 0, "Fbm: needs at least one octave");
+        self.octaves = octaves;
+        self
+    }
+
+    /// Sets how much the frequency grows from one octave to the next.
+    pub fn lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Sets how much the amplitude shrinks from one octave to the next
+    /// (also known as persistence).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gain` is not positive and finite.
+    pub fn gain(mut self, gain: f32) -> Self {
+        assert!(
+            gain > 0.0 && gain.is_finite(),
+            "Fbm: gain must be positive and finite"
+        );
+        self.gain = gain;
+        self
+    }
+
+    /// Adds up `sample(noise, frequency)` over every octave, scaled back
+    /// into `-1.0..=1.0`.
+    fn layer(&self, sample: impl Fn(&N, f32) -> f32) -> f32 {
+        let (mut sum, mut total_amplitude) = (0.0, 0.0);
+        let (mut amplitude, mut frequency) = (1.0, 1.0);
+        for _ in 0..self.octaves {
+            sum += amplitude * sample(&self.noise, frequency);
+            total_amplitude += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        bounded(sum / total_amplitude)
+    }
+}
+
+impl<N: Noise> Noise for Fbm<N> {
+    fn sample_1d(&self, x: f32) -> f32 {
+        self.layer(|noise, f| noise.sample_1d(x * f))
+    }
+
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        self.layer(|noise, f| noise.sample_2d(x * f, y * f))
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.layer(|noise, f| noise.sample_3d(x * f, y * f, z * f))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RandomNumberGenerator;
+
+    fn perlin(seed: u64) -> Perlin {
+        Perlin::new(&mut RandomNumberGenerator::seeded(seed))
+    }
+
+    fn simplex(seed: u64) -> Simplex {
+        Simplex::new(&mut RandomNumberGenerator::seeded(seed))
+    }
+
+    // points spread over several lattice cells, including negative ones
+    fn points() -> impl Iterator<Item = (f32, f32, f32)> {
+        (0..2000).map(|n| {
+            let n = n as f32;
+            (n * 0.173 - 150.0, n * 0.291 - 37.0, n * 0.057 + 3.0)
+        })
+    }
+
+    fn check_noise(noise: &impl Noise) {
+        let mut seen = (f32::MAX, f32::MIN);
+        for (x, y, z) in points() {
+            for value in [
+                noise.sample_1d(x),
+                noise.sample_2d(x, y),
+                noise.sample_3d(x, y, z),
+            ] {
+                assert!((-1.0..=1.0).contains(&value));
+                seen = (seen.0.min(value), seen.1.max(value));
+            }
+            // coherent: a tiny step only changes the value a little
+            assert!((noise.sample_1d(x) - noise.sample_1d(x + 0.001)).abs() < 0.05);
+            assert!((noise.sample_2d(x, y) - noise.sample_2d(x + 0.001, y)).abs() < 0.05);
+            assert!((noise.sample_3d(x, y, z) - noise.sample_3d(x, y, z + 0.001)).abs() < 0.05);
+        }
+        // and it actually varies
+        assert!(seen.0 < -0.3 && seen.1 > 0.3, "range too narrow: {seen:?}");
+    }
+
+    #[test]
+    fn test_perlin() {
+        check_noise(&perlin(1));
+        let noise = perlin(1);
+        for n in -5..5 {
+            let n = n as f32;
+            assert_eq!(noise.sample_1d(n), 0.0);
+            assert_eq!(noise.sample_2d(n, 3.0), 0.0);
+            assert_eq!(noise.sample_3d(1.0, n, -2.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_simplex() {
+        check_noise(&simplex(2));
+    }
+
+    #[test]
+    fn test_far_inputs() {
+        // past `i32::MAX` the lattice coordinates saturate; sums of three
+        // overflow from about 7e8
+        let far = [8e8, 2.2e9, -2.2e9, 1e12, -1e12, 1e30, -1e30];
+        let noises: [&dyn Noise; 3] = [&perlin(6), &simplex(6), &Fbm::new(perlin(6)).octaves(2)];
+        for noise in noises {
+            for x in far {
+                assert!((-1.0..=1.0).contains(&noise.sample_1d(x)));
+                assert!((-1.0..=1.0).contains(&noise.sample_2d(x, x)));
+                assert!((-1.0..=1.0).contains(&noise.sample_3d(x, x, x)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_not_finite_inputs() {
+        let noises: [&dyn Noise; 3] = [&perlin(7), &simplex(7), &Fbm::new(simplex(7))];
+        for noise in noises {
+            for x in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+                assert_eq!(noise.sample_1d(x), 0.0);
+                assert_eq!(noise.sample_2d(x, 0.5), 0.0);
+                assert_eq!(noise.sample_3d(0.5, 0.5, x), 0.0);
+            }
+        }
+        // a finite input whose higher octaves overflow still stays in range
+        let fbm = Fbm::new(perlin(7)).octaves(8);
+        assert!((-1.0..=1.0).contains(&fbm.sample_2d(f32::MAX, 1.0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "gain must be positive and finite")]
+    fn test_fbm_negative_gain() {
+        // with an even number of octaves the amplitudes would add up to 0
+        let _ = Fbm::new(perlin(8)).octaves(2).gain(-1.0);
+    }
+
+    #[test]
+    fn test_fbm() {
+        check_noise(&Fbm::new(perlin(3)).octaves(6));
+        check_noise(&Fbm::new(simplex(3)).lacunarity(1.9).gain(0.6));
+    }
+
+    #[test]
+    fn test_seeding() {
+        // the same seed gives the same world
+        let (a, b) = (perlin(4), perlin(4));
+        let (c, d) = (simplex(4), simplex(4));
+        for (x, y, z) in points() {
+            assert_eq!(a.sample_3d(x, y, z), b.sample_3d(x, y, z));
+            assert_eq!(c.sample_2d(x, y), d.sample_2d(x, y));
+        }
+        // a different seed gives a different one
+        let other = perlin(5);
+        assert!(points().any(|(x, y, _)| a.sample_2d(x, y) != other.sample_2d(x, y)));
+    }
+}
@@ -0,0 +1,252 @@
+//! Statistical checks of random number quality, for comparing the
+//! generator [`Algorithm`](crate::Algorithm)s.
+//!
+//! Each check returns a z-score: how many standard deviations its result
+//! lies from what a perfect generator would give on average. A good
+//! generator scores within about ±3; a broken one scores far outside.
+use crate::RandomSource;
+
+/// A z-score further from zero than this fails [`QualityReport::passes`].
+/// A perfect generator goes beyond it about once in 16,000 checks.
+pub const Z_LIMIT: f64 = 4.0;
+
+/// The span of [`QualityReport::small_range_bias`]: a six-sided die.
+pub const SMALL_SPAN: u64 = 6;
+
+/// The span of [`QualityReport::large_range_bias`]. Three quarters of the
+/// `u64` range, where reducing a raw draw with `%` would make the lowest
+/// values twice as likely as the rest.
+pub const LARGE_SPAN: u64 = 3 << 62;
+
+/// Spans with more values than this are grouped into this many buckets
+/// by [`range_bias`].
+const RANGE_BUCKETS: u64 = 16;
+
+/// Pearson's chi-square test: are `counts` spread evenly over their
+/// buckets? Returns the statistic as a z-score, using the Wilson–Hilferty
+/// approximation. Large positive scores mean some buckets are favored;
+/// large negative ones mean the counts are suspiciously even.
+///
+/// # Panics
+///
+/// Panics if there are fewer than two buckets or nothing was counted.
+pub fn chi_square_z(counts: &[u64]) -> f64 {
+    assert!(
+        counts.len() >= 2,
+        "chi_square_z: needs at least two buckets"
+    );
+    let total: u64 = counts.iter().sum();
+    assert!(total > 0, "chi_square_z: needs at least one count");
+    let expected = total as f64 / counts.len() as f64;
+    let chi_square: f64 = counts
+        .iter()
+        .map(|count| (*count as f64 - expected).powi(2) / expected)
+        .sum();
+    // the cube root of chi-square over its degrees of freedom is nearly normal
+    let freedom = (counts.len() - 1) as f64;
+    let spread = 2.0 / (9.0 * freedom);
+    ((chi_square / freedom).cbrt() - (1.0 - spread)) / spread.sqrt()
+}
+
+/// Lag-1 serial correlation: does each sample predict the next? Returns
+/// the correlation coefficient as a z-score.
+///
+/// # Panics
+///
+/// Panics if there are fewer than three samples, or they are all equal.
+pub fn serial_correlation_z(samples: &[f64]) -> f64 {
+    assert!(
+        samples.len() >= 3,
+        "serial_correlation_z: needs at least three samples"
+    );
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    let variance: f64 = samples.iter().map(|x| (x - mean).powi(2)).sum();
+    assert!(
+        variance > 0.0,
+        "serial_correlation_z: samples are all equal"
+    );
+    let covariance: f64 = samples
+        .windows(2)
+        .map(|pair| (pair[0] - mean) * (pair[1] - mean))
+        .sum();
+    covariance / variance * (samples.len() as f64).sqrt()
+}
+
+/// Wald–Wolfowitz runs test: do samples above and below `median` alternate
+/// as often as chance predicts? Returns the number of runs as a z-score.
+/// Large positive scores mean too much alternation; large negative ones
+/// mean values clump together.
+///
+/// # Panics
+///
+/// Panics unless there are samples on both sides of `median`.
+pub fn runs_z(samples: &[f64], median: f64) -> f64 {
+    let above = samples.iter().filter(|x| **x >= median).count() as f64;
+    let below = samples.len() as f64 - above;
+    assert!(
+        above > 0.0 && below > 0.0,
+        "runs_z: needs samples on both sides of the median"
+    );
+    let runs = 1 + samples
+        .windows(2)
+        .filter(|pair| (pair[0] >= median) != (pair[1] >= median))
+        .count();
+    let n = above + below;
+    let mean = 2.0 * above * below / n + 1.0;
+    let variance = (mean - 1.0) * (mean - 2.0) / (n - 1.0);
+    (runs as f64 - mean) / variance.sqrt()
+}
+
+/// Draws `range(0..span)` `draws` times and returns the worst relative
+/// deviation of any value's frequency from the fair one (`0.02` is 2% too
+/// common or too rare). Spans over 16 are grouped into 16 buckets.
+///
+/// # Panics
+///
+/// Panics if `span` is zero.
+pub fn range_bias(mut rng: impl RandomSource, span: u64, draws: usize) -> f64 {
+    assert!(span > 0, "range_bias: span must be positive");
+    let buckets = span.min(RANGE_BUCKETS);
+    let mut counts = vec![0u64; buckets as usize];
+    for _ in 0..draws {
+        let value = rng.range(0..span);
+        // widened, so large spans don't overflow
+        counts[(u128::from(value) * u128::from(buckets) / u128::from(span)) as usize] += 1;
+    }
+    // buckets differ in size by one value when `span` doesn't divide evenly
+    let first_value = |bucket: u128| (bucket * u128::from(span)).div_ceil(u128::from(buckets));
+    counts
+        .iter()
+        .enumerate()
+        .map(|(bucket, count)| {
+            let bucket = bucket as u128;
+            let values = first_value(bucket + 1) - first_value(bucket);
+            let expected = draws as f64 * values as f64 / span as f64;
+            (*count as f64 - expected).abs() / expected
+        })
+        .fold(0.0, f64::max)
+}
+
+/// The worst [`range_bias`] a perfect generator would plausibly show.
+fn range_bias_limit(span: u64, draws: usize) -> f64 {
+    Z_LIMIT * (span.min(RANGE_BUCKETS) as f64 / draws as f64).sqrt()
+}
+
+/// The results of every check on one generator.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{Algorithm, QualityReport, RandomNumberGenerator};
+/// for algorithm in Algorithm::ALL {
+///     let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 1);
+///     let report = QualityReport::measure(&mut rng, 10_000);
+///     assert!(report.passes(), "{algorithm:?}: {report:?}");
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityReport {
+    /// Number of draws used by each check.
+    pub samples: usize,
+    /// [`chi_square_z`] over the top 8 bits of `u32` draws.
+    pub chi_square_z: f64,
+    /// [`serial_correlation_z`] of `f64` draws.
+    pub serial_correlation_z: f64,
+    /// [`runs_z`] of `f64` draws around 0.5.
+    pub runs_z: f64,
+    /// [`range_bias`] over [`SMALL_SPAN`].
+    pub small_range_bias: f64,
+    /// [`range_bias`] over [`LARGE_SPAN`].
+    pub large_range_bias: f64,
+}
+
+impl QualityReport {
+    /// Runs every check, drawing `samples` values for each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `samples` is too small for the checks (under about 100).
+    pub fn measure(mut rng: impl RandomSource, samples: usize) -> Self {
+        let mut counts = [0u64; 256];
+        for _ in 0..samples {
+            counts[(rng.next::<u32>() >> 24) as usize] += 1;
+        }
+        let uniform: Vec<f64> = (0..samples).map(|_| rng.next()).collect();
+        Self {
+            samples,
+            chi_square_z: chi_square_z(&counts),
+            serial_correlation_z: serial_correlation_z(&uniform),
+            runs_z: runs_z(&uniform, 0.5),
+            small_range_bias: range_bias(&mut rng, SMALL_SPAN, samples),
+            large_range_bias: range_bias(&mut rng, LARGE_SPAN, samples),
+        }
+    }
+
+    /// `true` if every z-score is within [`Z_LIMIT`] and neither range bias
+    /// is larger than sampling noise explains.
+    pub fn passes(&self) -> bool {
+        [self.chi_square_z, self.serial_correlation_z, self.runs_z]
+            .iter()
+            .all(|z| z.abs() <= Z_LIMIT)
+            && self.small_range_bias <= range_bias_limit(SMALL_SPAN, self.samples)
+            && self.large_range_bias <= range_bias_limit(LARGE_SPAN, self.samples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Algorithm, RandomNumberGenerator};
+    use rand::rngs::mock::StepRng;
+
+    const SAMPLES: usize = 200_000;
+
+    // a "generator" that counts up: evenly spread, but utterly predictable
+    impl RandomSource for StepRng {}
+
+    #[test]
+    fn test_backends_pass() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 1);
+            let report = QualityReport::measure(&mut rng, SAMPLES);
+            assert!(report.passes(), "{algorithm:?} failed: {report:?}");
+        }
+    }
+
+    #[test]
+    fn test_detects_broken_generator() {
+        let report = QualityReport::measure(StepRng::new(0, 0x0123_4567_89ab_cdef), SAMPLES);
+        assert!(!report.passes());
+        assert!(report.serial_correlation_z.abs() > 100.0);
+    }
+
+    #[test]
+    fn test_chi_square() {
+        assert!(chi_square_z(&[1000, 0, 0, 0]) > 20.0);
+        // spread exactly evenly is too good to be random
+        assert!(chi_square_z(&[250; 256]) < -Z_LIMIT);
+    }
+
+    #[test]
+    fn test_runs() {
+        let alternating: Vec<f64> = (0..1000).map(|i| (i % 2) as f64).collect();
+        assert!(runs_z(&alternating, 0.5) > 20.0);
+        let clumped: Vec<f64> = (0..1000).map(|i| (i / 500) as f64).collect();
+        assert!(runs_z(&clumped, 0.5) < -20.0);
+    }
+
+    #[test]
+    fn test_serial_correlation() {
+        let rising: Vec<f64> = (0..1000).map(f64::from).collect();
+        assert!(serial_correlation_z(&rising) > 20.0);
+    }
+
+    #[test]
+    fn test_range_bias() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        for span in [1, 2, SMALL_SPAN, 100, LARGE_SPAN, u64::MAX] {
+            let bias = range_bias(&mut rng, span, SAMPLES);
+            assert!(bias <= range_bias_limit(span, SAMPLES), "{span}: {bias}");
+        }
+    }
+}
@@ -1,26 +1,23 @@
-use rand::{Rng, SeedableRng, distributions::uniform::SampleRange};
-
-/// `Random` is a Bevy plugin that inserts a `RandomNumberGenerator`
-/// resource into your application.
-///
-/// Once you add the plugin (with `App::new().add_plugin(Random)`),
-/// you can access a random number generator in systems with
-/// `rng: ResMut<RandomNumberGenerator>`.
-pub struct RandomPlugin;
-
-// library users can pick algo
-#[cfg(not(feature = "xorshift"))]
-type RngCore = rand::prelude::StdRng;
-
-#[cfg(feature = "xorshift")]
-type RngCore = rand_xorshift::XorShiftRng;
+use crate::{
+    Algorithm, DiceExpr, DiceParseError, DiceRoll, DrawLog, RandomSource, algorithm::AlgorithmRng,
+    desync::Tracked, distributions, geometry, sampling, seeding::derive_seed,
+};
+use bevy::{
+    color::Color,
+    math::{Dir2, Dir3, Quat, Rect, Vec2},
+};
+use rand::{Rng, distributions::uniform::SampleRange};
+use std::ops::Range;
 
 /// `RandomNumberGenerator` holds random number generation state, and offers
 /// random number generation services to your program.
 ///
 /// `RandomNumberGenerator` defaults to using the
 /// [PCG](https://crates.io/crates/rand_pcg) algorithm.
-/// You can specify `xorshift` as a feature flag to use it instead.
+/// You can specify `xorshift` as a feature flag to make it the default
+/// instead (with default features turned off, as only one algorithm may be
+/// enabled), or pick any [`Algorithm`] at runtime with
+/// [`RandomNumberGenerator::with_algorithm`].
 ///
 /// By default, `RandomNumberGenerator` requires mutability---it
 /// is shared in Bevy with `ResMut<RandomNumberGenerator>`. If
@@ -37,17 +34,36 @@ type RngCore = rand_xorshift::XorShiftRng;
 /// println!("{random_number}");
 /// ```
 #[derive(bevy::prelude::Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RandomNumberGenerator {
-    rng: RngCore,
+    rng: AlgorithmRng,
+    seed: u64,
+    // debugging aid, not part of the generator's state
+    #[cfg_attr(feature = "serde", serde(skip))]
+    draw_log: Option<DrawLog>,
+}
+
+/// `RngSnapshot` is a saved position of a [`RandomNumberGenerator`],
+/// taken with [`RandomNumberGenerator::snapshot`]. Restoring it makes the
+/// generator continue the exact sequence it would have produced.
+///
+/// With the `serde` feature, snapshots can be serialized (e.g. into a
+/// save game). A snapshot records its algorithm, so restoring it also
+/// switches the generator to that algorithm. Generator state contains
+/// 128-bit integers, so pick a format that supports them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RngSnapshot {
+    rng: AlgorithmRng,
+    seed: u64,
 }
 
 impl RandomNumberGenerator {
     /// Creates a default `RandomNumberGenerator`, with a randomly
     /// selected starting seed.
     pub fn new() -> Self {
-        Self {
-            rng: RngCore::from_entropy(),
-        }
+        // pick the seed from entropy, so it can be reported and forked from
+        Self::seeded(rand::random())
     }
 
     /// Creates a new `RandomNumberGenerator`, with a user-specified random seed.
@@ -67,18 +83,78 @@ impl RandomNumberGenerator {
     /// assert_eq!(results.0, results.1);
     /// ```
     pub fn seeded(seed: u64) -> Self {
+        Self::with_algorithm(Algorithm::default(), seed)
+    }
+
+    /// Creates a new `RandomNumberGenerator` that uses the given algorithm,
+    /// rather than the one picked by feature flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the random number generation algorithm to use.
+    /// * `seed` - the random seed to use.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::{Algorithm, RandomNumberGenerator};
+    /// let mut rng = RandomNumberGenerator::with_algorithm(Algorithm::XorShift, 1);
+    /// let one_to_six = rng.range(1..=6);
+    /// ```
+    pub fn with_algorithm(algorithm: Algorithm, seed: u64) -> Self {
         Self {
-            rng: RngCore::seed_from_u64(seed),
+            rng: AlgorithmRng::seeded(algorithm, seed),
+            seed,
+            draw_log: None,
         }
     }
 
+    /// The algorithm this generator uses.
+    pub fn algorithm(&self) -> Algorithm {
+        self.rng.algorithm()
+    }
+
+    /// The seed this generator was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Creates an independent child generator for the named stream. The
+    /// child's seed is derived from this generator's seed and `label` only,
+    /// so it is the same no matter how many numbers have been drawn from
+    /// this generator (or from other streams) so far.
+    ///
+    /// Give each subsystem its own stream, and adding randomness to one
+    /// (e.g. particles) no longer changes the results of another (e.g. the
+    /// level layout) for a given seed. Streams can be forked further, and
+    /// use the same algorithm as their parent.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - the name of the stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::seeded(1);
+    /// let mut walls = rng.fork("walls");
+    /// let _ = rng.range(0..100);
+    /// let mut walls_again = rng.fork("walls");
+    /// assert_eq!(walls.next::<u32>(), walls_again.next::<u32>());
+    /// ```
+    pub fn fork(&self, label: &str) -> Self {
+        Self::with_algorithm(self.algorithm(), derive_seed(self.seed, label))
+    }
+
     /// Generates a new random number of the requested type.
+    #[allow(clippy::should_implement_trait)]
     pub fn next<T>(&mut self) -> T
     where
         rand::distributions::Standard: rand::prelude::Distribution<T>,
     {
         // gen is a keyword, need to escape it to use as identifier
-        self.rng.r#gen()
+        self.stream().r#gen()
     }
 
     /// Generates a random number within the specified range.
@@ -100,19 +176,336 @@ impl RandomNumberGenerator {
     where
         T: rand::distributions::uniform::SampleUniform + PartialOrd,
     {
-        self.rng.gen_range(range)
+        self.stream().gen_range(range)
+    }
+
+    /// Samples a normal (gaussian) distribution: values near `mean` are
+    /// most likely, and about 68% fall within `std_dev` of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `std_dev` is negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::new();
+    /// let gap = rng.gaussian_clamped(0.0, 2.0, -5.0, 5.0);
+    /// assert!((-5.0..=5.0).contains(&gap));
+    /// ```
+    pub fn gaussian(&mut self, mean: f32, std_dev: f32) -> f32 {
+        distributions::gaussian(&mut self.stream(), mean, std_dev)
+    }
+
+    /// Like [`Self::gaussian`], but clamped to `min..=max` so the rare
+    /// outlier can't break gameplay.
+    pub fn gaussian_clamped(&mut self, mean: f32, std_dev: f32, min: f32, max: f32) -> f32 {
+        distributions::gaussian_clamped(&mut self.stream(), mean, std_dev, min, max)
+    }
+
+    /// Samples an exponential distribution with the given mean: the time
+    /// between events that happen at random at a steady rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mean` is not positive.
+    pub fn exponential(&mut self, mean: f32) -> f32 {
+        distributions::exponential(&mut self.stream(), mean)
+    }
+
+    /// Samples a Poisson distribution with the given mean: how many events
+    /// happen in an interval, if they happen `mean` times on average.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mean` is not positive and finite.
+    pub fn poisson(&mut self, mean: f32) -> u32 {
+        distributions::poisson(&mut self.stream(), mean)
+    }
+
+    /// Samples a triangular distribution between `min` and `max`, most
+    /// likely to be near `mode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `min <= mode <= max`.
+    pub fn triangular(&mut self, min: f32, max: f32, mode: f32) -> f32 {
+        distributions::triangular(&mut self.stream(), min, max, mode)
+    }
+
+    /// Averages `rolls` uniform draws from `range`, like averaging `NdM`
+    /// dice: more rolls give a tighter bell curve around the middle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rolls` is zero or `range` is empty.
+    pub fn bell_curve(&mut self, range: Range<f32>, rolls: u32) -> f32 {
+        distributions::bell_curve(&mut self.stream(), range, rolls)
+    }
+
+    /// Shuffles `items` in place.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::seeded(1);
+    /// let mut deck: Vec<u32> = (1..=52).collect();
+    /// rng.shuffle(&mut deck);
+    /// let hand = rng.sample(&deck, 5);
+    /// assert_eq!(hand.len(), 5);
+    /// ```
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        sampling::shuffle(&mut self.stream(), items)
+    }
+
+    /// Picks one item, or `None` if `items` is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        sampling::choose(&mut self.stream(), items)
+    }
+
+    /// Picks one item, with a chance proportional to its `weight`. Returns
+    /// `None` if `items` is empty, the weights add up to zero, or a weight
+    /// is negative. Use a [`RandomTable`](crate::RandomTable) for tables
+    /// you roll on repeatedly.
+    pub fn choose_weighted<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> f32,
+    ) -> Option<&'a T> {
+        sampling::choose_weighted(&mut self.stream(), items, weight)
+    }
+
+    /// Picks `n` distinct items (or all of them, if there are fewer),
+    /// in random order.
+    pub fn sample<'a, T>(&mut self, items: &'a [T], n: usize) -> Vec<&'a T> {
+        sampling::sample(&mut self.stream(), items, n)
+    }
+
+    /// Picks one item from an iterator of unknown length, in a single pass
+    /// (reservoir sampling). Returns `None` if the iterator is empty.
+    pub fn choose_iter<I: IntoIterator>(&mut self, items: I) -> Option<I::Item> {
+        sampling::choose_iter(&mut self.stream(), items)
+    }
+
+    /// Picks `n` distinct items from an iterator in a single pass
+    /// (reservoir sampling). The result's order is not random.
+    pub fn sample_iter<I: IntoIterator>(&mut self, items: I, n: usize) -> Vec<I::Item> {
+        sampling::sample_iter(&mut self.stream(), items, n)
+    }
+
+    /// A point anywhere inside `rect`, evenly spread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::math::{Rect, Vec2};
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::seeded(1);
+    /// let spawn = rng.point_in_rect(Rect::new(-100.0, -50.0, 100.0, 50.0));
+    /// assert!(spawn.x.abs() <= 100.0 && spawn.y.abs() <= 50.0);
+    /// let splash = rng.point_in_circle(Vec2::ZERO, 10.0);
+    /// assert!(splash.length() <= 10.0);
+    /// ```
+    pub fn point_in_rect(&mut self, rect: Rect) -> Vec2 {
+        geometry::point_in_rect(&mut self.stream(), rect)
+    }
+
+    /// A point inside a circle, evenly spread over its area.
+    pub fn point_in_circle(&mut self, center: Vec2, radius: f32) -> Vec2 {
+        geometry::point_in_circle(&mut self.stream(), center, radius)
+    }
+
+    /// A point on the edge of a circle.
+    pub fn point_on_circle(&mut self, center: Vec2, radius: f32) -> Vec2 {
+        geometry::point_on_circle(&mut self.stream(), center, radius)
+    }
+
+    /// A 2D direction, with every angle equally likely.
+    pub fn direction_2d(&mut self) -> Dir2 {
+        geometry::direction_2d(&mut self.stream())
+    }
+
+    /// A 3D direction, evenly spread over the sphere.
+    pub fn direction_3d(&mut self) -> Dir3 {
+        geometry::direction_3d(&mut self.stream())
+    }
+
+    /// A rotation, with every orientation equally likely.
+    pub fn rotation(&mut self) -> Quat {
+        geometry::rotation(&mut self.stream())
+    }
+
+    /// A color whose hue (in degrees), saturation and lightness are drawn
+    /// from the given ranges.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::seeded(1);
+    /// // any shade of green, never grey, black or white
+    /// let leaf = rng.color_hsl(90.0..150.0, 0.5..=1.0, 0.3..0.6);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is empty.
+    pub fn color_hsl(
+        &mut self,
+        hue: impl SampleRange<f32>,
+        saturation: impl SampleRange<f32>,
+        lightness: impl SampleRange<f32>,
+    ) -> Color {
+        geometry::color_hsl(&mut self.stream(), hue, saturation, lightness)
+    }
+
+    /// Scatters points over `rect`, no two closer than `min_distance`, with
+    /// no room left for another (Poisson-disc sampling). Good for placing
+    /// trees, rocks or pickups without clumps or gaps. Returns nothing for
+    /// a rect with no area.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::math::Rect;
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::seeded(1);
+    /// let trees = rng.poisson_disc(Rect::new(0.0, 0.0, 200.0, 200.0), 16.0);
+    /// assert!(trees.len() > 50);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_distance` is not positive and finite, or if it is so
+    /// small for `rect` that the search grid, of cells `min_distance / √2`
+    /// wide, would need more than 2²² (about four million) cells.
+    pub fn poisson_disc(&mut self, rect: Rect, min_distance: f32) -> Vec<Vec2> {
+        geometry::poisson_disc(&mut self.stream(), rect, min_distance)
+    }
+
+    /// Captures the generator's current position.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::seeded(1);
+    /// let saved = rng.snapshot();
+    /// let first: u32 = rng.next();
+    /// rng.restore(&saved);
+    /// assert_eq!(first, rng.next::<u32>());
+    /// ```
+    pub fn snapshot(&self) -> RngSnapshot {
+        RngSnapshot {
+            rng: self.rng.clone(),
+            seed: self.seed,
+        }
+    }
+
+    /// Returns the generator to a position captured by [`Self::snapshot`].
+    /// The seed is restored too, so forked streams match the saved generator.
+    pub fn restore(&mut self, snapshot: &RngSnapshot) {
+        self.rng = snapshot.rng.clone();
+        self.seed = snapshot.seed;
+    }
+
+    /// Creates a new generator that continues from a saved position.
+    pub fn from_snapshot(snapshot: &RngSnapshot) -> Self {
+        Self {
+            rng: snapshot.rng.clone(),
+            seed: snapshot.seed,
+            draw_log: None,
+        }
+    }
+
+    /// Starts counting draws and hashing the values drawn, from zero (even
+    /// if the draw log was already running), for desync detection. See
+    /// [`DrawLog`].
+    ///
+    /// The log is a debugging aid rather than part of the generator's
+    /// state: snapshots, serialization and forks leave it out, and
+    /// restoring a snapshot doesn't rewind it.
+    pub fn start_draw_log(&mut self) {
+        self.draw_log = Some(DrawLog::default());
+    }
+
+    /// Stops the draw log started by [`Self::start_draw_log`].
+    pub fn stop_draw_log(&mut self) {
+        self.draw_log = None;
+    }
+
+    /// Draws made since [`Self::start_draw_log`], and their checksum.
+    /// `None` while the draw log is off.
+    pub fn draw_log(&self) -> Option<DrawLog> {
+        self.draw_log
+    }
+
+    // every draw goes through here, so the draw log sees it
+    fn stream(&mut self) -> Tracked<'_, Option<&mut DrawLog>> {
+        Tracked {
+            rng: &mut self.rng,
+            log: self.draw_log.as_mut(),
+        }
+    }
+
+    /// Rolls dice written in dice notation, such as `3d6+2` or `4d6kh3`.
+    /// See [`DiceExpr`] for the supported notation.
+    ///
+    /// # Arguments
+    ///
+    /// * `notation` - the dice expression to parse and roll.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let mut rng = RandomNumberGenerator::new();
+    /// let roll = rng.roll_dice("3d6+2").unwrap();
+    /// assert!((5..=20).contains(&roll.total));
+    /// assert_eq!(roll.dice().count(), 3);
+    /// ```
+    pub fn roll_dice(&mut self, notation: &str) -> Result<DiceRoll, DiceParseError> {
+        let dice: DiceExpr = notation.parse()?;
+        Ok(self.roll(&dice))
+    }
+
+    /// Rolls an already parsed dice expression. Parse once with
+    /// `"3d6".parse::<DiceExpr>()` if you roll the same dice repeatedly.
+    pub fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        let mut rng = self.stream();
+        dice.roll_with(|sides| rng.gen_range(1..=sides))
     }
 }
 
-impl Default for RandomNumberGenerator {
-    fn default() -> Self {
-        Self::new()
+impl rand::RngCore for RandomNumberGenerator {
+    fn next_u32(&mut self) -> u32 {
+        self.stream().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.stream().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.stream().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.stream().try_fill_bytes(dest)
+    }
+}
+
+impl RandomSource for RandomNumberGenerator {
+    fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        RandomNumberGenerator::roll(self, dice)
     }
 }
 
-impl bevy::prelude::Plugin for RandomPlugin {
-    fn build(&self, app: &mut bevy::prelude::App) {
-        app.insert_resource(RandomNumberGenerator::new());
+impl Default for RandomNumberGenerator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -132,16 +525,36 @@ mod test {
 
     #[test]
     fn test_reproducibility() {
-        let mut rng = (
-            RandomNumberGenerator::seeded(1),
-            RandomNumberGenerator::seeded(1),
-        );
-        (0..1000).for_each(|_| {
-            assert_eq!(
-                rng.0.range(u32::MIN..u32::MAX),
-                rng.1.range(u32::MIN..u32::MAX)
+        for algorithm in Algorithm::ALL {
+            let mut rng = (
+                RandomNumberGenerator::with_algorithm(algorithm, 1),
+                RandomNumberGenerator::with_algorithm(algorithm, 1),
             );
+            (0..1000).for_each(|_| {
+                assert_eq!(
+                    rng.0.range(u32::MIN..u32::MAX),
+                    rng.1.range(u32::MIN..u32::MAX)
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn test_backend_sequence() {
+        // `seeded` must hand out exactly what the default backend produces
+        let mut rng = RandomNumberGenerator::seeded(42);
+        assert_eq!(rng.algorithm(), Algorithm::default());
+        let mut backend = AlgorithmRng::seeded(Algorithm::default(), 42);
+        (0..1000).for_each(|_| {
+            assert_eq!(rng.next::<u64>(), backend.r#gen::<u64>());
         });
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 42);
+            let mut backend = AlgorithmRng::seeded(algorithm, 42);
+            (0..1000).for_each(|_| {
+                assert_eq!(rng.next::<u64>(), backend.r#gen::<u64>());
+            });
+        }
     }
 
     #[test]
@@ -161,4 +574,135 @@ mod test {
         let _: i32 = rng.next();
         let _ = rng.next::<f32>();
     }
+
+    #[test]
+    fn test_roll_dice() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 3);
+            for _ in 0..1000 {
+                let roll = rng.roll_dice("4d6kh3+2").unwrap();
+                assert!((5..=20).contains(&roll.total));
+                assert_eq!(roll.dice().filter(|d| d.kept).count(), 3);
+            }
+            assert!(rng.roll_dice("3d").is_err());
+        }
+    }
+
+    #[test]
+    fn test_roll_reproducibility() {
+        for algorithm in Algorithm::ALL {
+            let dice: DiceExpr = "10d6!+1d20ro1".parse().unwrap();
+            let mut rng = (
+                RandomNumberGenerator::with_algorithm(algorithm, 7),
+                RandomNumberGenerator::with_algorithm(algorithm, 7),
+            );
+            (0..100).for_each(|_| assert_eq!(rng.0.roll(&dice), rng.1.roll(&dice)));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 9);
+            (0..37).for_each(|_| {
+                rng.next::<u64>();
+            });
+            let saved = rng.snapshot();
+            let expected: Vec<u64> = (0..1000).map(|_| rng.next()).collect();
+
+            rng.restore(&saved);
+            let replayed: Vec<u64> = (0..1000).map(|_| rng.next()).collect();
+            assert_eq!(expected, replayed);
+
+            // a different generator picks up the same sequence
+            let mut other = RandomNumberGenerator::with_algorithm(algorithm, 10);
+            other.restore(&saved);
+            assert_eq!(expected[0], other.next::<u64>());
+            let mut fresh = RandomNumberGenerator::from_snapshot(&saved);
+            assert_eq!(expected[0], fresh.next::<u64>());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 11);
+            rng.range(0..100);
+            let saved_rng = ron::to_string(&rng).unwrap();
+            let saved_snapshot = ron::to_string(&rng.snapshot()).unwrap();
+            let expected: Vec<u64> = (0..1000).map(|_| rng.next()).collect();
+
+            let mut restored: RandomNumberGenerator = ron::from_str(&saved_rng).unwrap();
+            let replayed: Vec<u64> = (0..1000).map(|_| restored.next()).collect();
+            assert_eq!(expected, replayed);
+
+            let snapshot: RngSnapshot = ron::from_str(&saved_snapshot).unwrap();
+            restored.restore(&snapshot);
+            let replayed: Vec<u64> = (0..1000).map(|_| restored.next()).collect();
+            assert_eq!(expected, replayed);
+        }
+    }
+
+    #[test]
+    fn test_fork() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 12);
+            assert_eq!(rng.seed(), 12);
+            let mut walls = rng.fork("walls");
+            let expected: Vec<u32> = (0..100).map(|_| walls.next()).collect();
+
+            // drawing from the parent or other streams doesn't move a stream
+            (0..100).for_each(|_| {
+                rng.next::<u32>();
+            });
+            let mut particles = rng.fork("particles");
+            let mut walls = rng.fork("walls");
+            let replayed: Vec<u32> = (0..100).map(|_| walls.next()).collect();
+            assert_eq!(expected, replayed);
+            let other: Vec<u32> = (0..100).map(|_| particles.next()).collect();
+            assert_ne!(expected, other);
+
+            // a different master seed gives different streams
+            let mut walls = RandomNumberGenerator::with_algorithm(algorithm, 13).fork("walls");
+            let other: Vec<u32> = (0..100).map(|_| walls.next()).collect();
+            assert_ne!(expected, other);
+
+            // nested forks are deterministic too
+            assert_eq!(
+                rng.fork("ai").fork("pathing").seed(),
+                RandomNumberGenerator::with_algorithm(algorithm, 12)
+                    .fork("ai")
+                    .fork("pathing")
+                    .seed()
+            );
+        }
+    }
+
+    #[test]
+    fn test_algorithm_follows_snapshot() {
+        let mut rng = RandomNumberGenerator::with_algorithm(Algorithm::XorShift, 20);
+        assert_eq!(rng.fork("walls").algorithm(), Algorithm::XorShift);
+        let saved = RandomNumberGenerator::with_algorithm(Algorithm::ChaCha12, 21).snapshot();
+        rng.restore(&saved);
+        assert_eq!(rng.algorithm(), Algorithm::ChaCha12);
+        assert_eq!(
+            rng.next::<u64>(),
+            AlgorithmRng::seeded(Algorithm::ChaCha12, 21).r#gen::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_snapshot_keeps_seed() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 14);
+            let mut restored = RandomNumberGenerator::with_algorithm(algorithm, 15);
+            restored.restore(&rng.snapshot());
+            assert_eq!(restored.seed(), 14);
+            assert_eq!(
+                restored.fork("walls").next::<u64>(),
+                rng.fork("walls").next::<u64>()
+            );
+        }
+    }
 }
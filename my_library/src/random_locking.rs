@@ -1,57 +1,750 @@
-use rand::{Rng, SeedableRng, distributions::uniform::SampleRange};
-use std::sync::Mutex;
+use crate::{
+    Algorithm, DiceExpr, DiceParseError, DiceRoll, DrawLog, RandomSource, algorithm::AlgorithmRng,
+    desync::Tracked, distributions, geometry, sampling, seeding::derive_seed,
+};
+use bevy::{
+    color::Color,
+    math::{Dir2, Dir3, Quat, Rect, Vec2},
+};
+use rand::{Rng, distributions::uniform::SampleRange};
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
 
-pub struct RandomPlugin;
+/// `RandomNumberGenerator` holds random number generation state, and offers
+/// random number generation services to your program.
+///
+/// This is the `locking` build: every method takes `&self`, so the generator
+/// is shared in Bevy with `Res<RandomNumberGenerator>`. Its own stream sits
+/// behind a `Mutex` that each method holds for that one call, so a call such
+/// as [`shuffle`](Self::shuffle) or [`roll`](Self::roll) draws all its
+/// numbers in one go, but systems drawing from the generator at the same time
+/// take turns. Give busy systems a named [`stream`](Self::stream) each: every
+/// stream has a lock of its own.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::RandomNumberGenerator;
+/// let my_rng = RandomNumberGenerator::new();
+/// let random_number = my_rng.range(1..10);
+/// println!("{random_number}");
+/// ```
+// The named streams sit in a registry of their own, locked before the
+// generator's stream or any named stream when both are needed.
+#[derive(bevy::prelude::Resource)]
+pub struct RandomNumberGenerator {
+    main: Mutex<Stream>,
+    // by label, so snapshots and draw logs visit streams in a stable order
+    streams: RwLock<BTreeMap<String, RngStream>>,
+}
 
-// library users can pick algo
-#[cfg(not(feature = "xorshift"))]
-type RngCore = rand::prelude::StdRng;
+/// `RngSnapshot` is a saved position of a [`RandomNumberGenerator`] and of
+/// each of its named streams, taken with [`RandomNumberGenerator::snapshot`].
+/// Restoring it makes the generator and the streams continue the exact
+/// sequences they would have produced.
+///
+/// With the `serde` feature, snapshots can be serialized (e.g. into a
+/// save game). A snapshot records its algorithm, so restoring it also
+/// switches the generator to that algorithm. Generator state contains
+/// 128-bit integers, so pick a format that supports them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RngSnapshot {
+    rng: AlgorithmRng,
+    seed: u64,
+    // named streams' positions; the seeds are derived again on restore
+    #[cfg_attr(feature = "serde", serde(default))]
+    streams: BTreeMap<String, AlgorithmRng>,
+}
 
-#[cfg(feature = "xorshift")]
-type RngCore = rand_xorshift::XorShiftRng;
+/// `RngStream` is one of a [`RandomNumberGenerator`]'s named streams,
+/// returned by [`RandomNumberGenerator::stream`]. It starts where
+/// [`fork`](RandomNumberGenerator::fork) with the same label would, but it
+/// stays registered with its generator: snapshots save its position,
+/// restoring one moves it back, and its draws count towards the generator's
+/// [`DrawLog`].
+///
+/// Each stream has a lock of its own, so systems drawing from different
+/// streams run in parallel without waiting on each other. Clones share the
+/// stream. Draw from it through the [`RandomSource`] trait.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{RandomNumberGenerator, RandomSource};
+/// let rng = RandomNumberGenerator::seeded(1);
+/// let mut walls = rng.stream("walls");
+/// let saved = rng.snapshot();
+/// let first = walls.range(0..100);
+/// rng.restore(&saved);
+/// assert_eq!(walls.range(0..100), first);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RngStream(Arc<Mutex<Stream>>);
 
-#[derive(bevy::prelude::Resource)]
-pub struct RandomNumberGenerator {
-    rng: Mutex<RngCore>,
+#[derive(Debug)]
+struct Stream {
+    rng: AlgorithmRng,
+    seed: u64,
+    // debugging aid, not part of the generator's state
+    draw_log: Option<DrawLog>,
+}
+
+impl Stream {
+    fn seeded(algorithm: Algorithm, seed: u64) -> Self {
+        Self {
+            rng: AlgorithmRng::seeded(algorithm, seed),
+            seed,
+            draw_log: None,
+        }
+    }
+
+    // the child stream `label`, at its start, logging if its parent is
+    fn fork(&self, label: &str) -> Self {
+        Self {
+            draw_log: self.draw_log.map(|_| DrawLog::default()),
+            ..Self::seeded(self.rng.algorithm(), derive_seed(self.seed, label))
+        }
+    }
+
+    fn with_rng<R>(&mut self, f: impl FnOnce(&mut Tracked<'_, Option<&mut DrawLog>>) -> R) -> R {
+        f(&mut Tracked {
+            rng: &mut self.rng,
+            log: self.draw_log.as_mut(),
+        })
+    }
+}
+
+// a panic mid-draw can't leave a stream in a bad state
+fn lock(stream: &Mutex<Stream>) -> MutexGuard<'_, Stream> {
+    stream.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
 impl RandomNumberGenerator {
+    /// Creates a default `RandomNumberGenerator`, with a randomly
+    /// selected starting seed.
     pub fn new() -> Self {
+        Self::seeded(rand::random())
+    }
+
+    /// Creates a new `RandomNumberGenerator`, with a user-specified random seed.
+    /// It will produce the same results each time (given the same requests).
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - the random seed to use.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng1 = RandomNumberGenerator::seeded(1);
+    /// let rng2 = RandomNumberGenerator::seeded(1);
+    /// let results: (u32, u32) = ( rng1.next(), rng2.next() );
+    /// assert_eq!(results.0, results.1);
+    /// ```
+    pub fn seeded(seed: u64) -> Self {
+        Self::with_algorithm(Algorithm::default(), seed)
+    }
+
+    /// Creates a new `RandomNumberGenerator` that uses the given algorithm,
+    /// rather than the one picked by feature flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the random number generation algorithm to use.
+    /// * `seed` - the random seed to use.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::{Algorithm, RandomNumberGenerator};
+    /// let rng = RandomNumberGenerator::with_algorithm(Algorithm::XorShift, 1);
+    /// let one_to_six = rng.range(1..=6);
+    /// ```
+    pub fn with_algorithm(algorithm: Algorithm, seed: u64) -> Self {
         Self {
-            rng: Mutex::new(RngCore::from_entropy()),
+            main: Mutex::new(Stream::seeded(algorithm, seed)),
+            streams: RwLock::default(),
         }
     }
 
-    pub fn seeded(seed: u64) -> Self {
+    fn with_rng<R>(&self, f: impl FnOnce(&mut Tracked<'_, Option<&mut DrawLog>>) -> R) -> R {
+        lock(&self.main).with_rng(f)
+    }
+
+    fn streams(&self) -> RwLockReadGuard<'_, BTreeMap<String, RngStream>> {
+        self.streams.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn streams_mut(&self) -> RwLockWriteGuard<'_, BTreeMap<String, RngStream>> {
+        self.streams.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The seed this generator was created with, or restored to.
+    pub fn seed(&self) -> u64 {
+        lock(&self.main).seed
+    }
+
+    /// The algorithm this generator uses.
+    pub fn algorithm(&self) -> Algorithm {
+        lock(&self.main).rng.algorithm()
+    }
+
+    /// Creates an independent child generator for the named stream. The
+    /// child's seed is derived from this generator's seed and `label` only,
+    /// so it is the same no matter how many numbers have been drawn from
+    /// this generator (or from other streams) so far.
+    ///
+    /// The child has locks of its own and isn't tracked by this generator: its
+    /// position isn't in this generator's snapshots and its draws aren't in
+    /// this generator's [`DrawLog`]. Use [`stream`](Self::stream) for a child
+    /// that is.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - the name of the stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::seeded(1);
+    /// let walls = rng.fork("walls");
+    /// let _ = rng.range(0..100);
+    /// let walls_again = rng.fork("walls");
+    /// assert_eq!(walls.next::<u32>(), walls_again.next::<u32>());
+    /// ```
+    pub fn fork(&self, label: &str) -> Self {
         Self {
-            rng: Mutex::new(RngCore::seed_from_u64(seed)),
+            main: Mutex::new(Stream {
+                draw_log: None,
+                ..lock(&self.main).fork(label)
+            }),
+            streams: RwLock::default(),
         }
     }
 
+    /// The named stream `label`, created at the position
+    /// [`fork`](Self::fork) would start it from the first time it is asked
+    /// for. Every call with the same label returns the same stream. See
+    /// [`RngStream`].
+    ///
+    /// Draws from the stream take only the stream's own lock, never the
+    /// generator's, so keep the stream (in a `Local`, or through
+    /// [`SystemRng`](crate::SystemRng)) rather than looking it up for
+    /// every draw. Looking one up takes a shared lock on the list of
+    /// streams; creating one briefly locks the list and the generator.
+    pub fn stream(&self, label: &str) -> RngStream {
+        if let Some(stream) = self.streams().get(label) {
+            return stream.clone();
+        }
+        let mut streams = self.streams_mut();
+        streams
+            .entry(label.to_string())
+            .or_insert_with(|| RngStream(Arc::new(Mutex::new(lock(&self.main).fork(label)))))
+            .clone()
+    }
+
+    /// Generates a new random number of the requested type.
     pub fn next<T>(&self) -> T
     where
         rand::distributions::Standard: rand::prelude::Distribution<T>,
     {
-        self.rng.lock().unwrap().r#gen()
+        self.with_rng(|rng| rng.r#gen())
     }
-
+    /// Generates a random number within the specified range.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - the range (inclusive or exclusive) within which to
+    ///   generate a random number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::new();
+    /// let one_to_nine = rng.range(1..10);
+    /// let one_to_ten = rng.range(1..=10);
+    /// ```
     pub fn range<T>(&self, range: impl SampleRange<T>) -> T
     where
         T: rand::distributions::uniform::SampleUniform + PartialOrd,
     {
-        self.rng.lock().unwrap().gen_range(range)
+        self.with_rng(|rng| rng.gen_range(range))
+    }
+
+    /// Samples a normal (gaussian) distribution: values near `mean` are
+    /// most likely, and about 68% fall within `std_dev` of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `std_dev` is negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::new();
+    /// let gap = rng.gaussian_clamped(0.0, 2.0, -5.0, 5.0);
+    /// assert!((-5.0..=5.0).contains(&gap));
+    /// ```
+    pub fn gaussian(&self, mean: f32, std_dev: f32) -> f32 {
+        self.with_rng(|rng| distributions::gaussian(rng, mean, std_dev))
+    }
+
+    /// Like [`Self::gaussian`], but clamped to `min..=max` so the rare
+    /// outlier can't break gameplay.
+    pub fn gaussian_clamped(&self, mean: f32, std_dev: f32, min: f32, max: f32) -> f32 {
+        self.with_rng(|rng| distributions::gaussian_clamped(rng, mean, std_dev, min, max))
+    }
+
+    /// Samples an exponential distribution with the given mean: the time
+    /// between events that happen at random at a steady rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mean` is not positive.
+    pub fn exponential(&self, mean: f32) -> f32 {
+        self.with_rng(|rng| distributions::exponential(rng, mean))
+    }
+
+    /// Samples a Poisson distribution with the given mean: how many events
+    /// happen in an interval, if they happen `mean` times on average.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mean` is not positive and finite.
+    pub fn poisson(&self, mean: f32) -> u32 {
+        self.with_rng(|rng| distributions::poisson(rng, mean))
+    }
+
+    /// Samples a triangular distribution between `min` and `max`, most
+    /// likely to be near `mode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `min <= mode <= max`.
+    pub fn triangular(&self, min: f32, max: f32, mode: f32) -> f32 {
+        self.with_rng(|rng| distributions::triangular(rng, min, max, mode))
+    }
+
+    /// Averages `rolls` uniform draws from `range`, like averaging `NdM`
+    /// dice: more rolls give a tighter bell curve around the middle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rolls` is zero or `range` is empty.
+    pub fn bell_curve(&self, range: Range<f32>, rolls: u32) -> f32 {
+        self.with_rng(|rng| distributions::bell_curve(rng, range, rolls))
+    }
+
+    /// Shuffles `items` in place, holding the generator's lock throughout.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::seeded(1);
+    /// let mut deck: Vec<u32> = (1..=52).collect();
+    /// rng.shuffle(&mut deck);
+    /// let hand = rng.sample(&deck, 5);
+    /// assert_eq!(hand.len(), 5);
+    /// ```
+    pub fn shuffle<T>(&self, items: &mut [T]) {
+        self.with_rng(|rng| sampling::shuffle(rng, items))
+    }
+
+    /// Picks one item, or `None` if `items` is empty.
+    pub fn choose<'a, T>(&self, items: &'a [T]) -> Option<&'a T> {
+        self.with_rng(|rng| sampling::choose(rng, items))
+    }
+
+    /// Picks one item, with a chance proportional to its `weight`. Returns
+    /// `None` if `items` is empty, the weights add up to zero, or a weight
+    /// is negative. Use a [`RandomTable`](crate::RandomTable) for tables
+    /// you roll on repeatedly.
+    pub fn choose_weighted<'a, T>(
+        &self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> f32,
+    ) -> Option<&'a T> {
+        self.with_rng(|rng| sampling::choose_weighted(rng, items, weight))
+    }
+
+    /// Picks `n` distinct items (or all of them, if there are fewer),
+    /// in random order.
+    pub fn sample<'a, T>(&self, items: &'a [T], n: usize) -> Vec<&'a T> {
+        self.with_rng(|rng| sampling::sample(rng, items, n))
+    }
+
+    /// Picks one item from an iterator of unknown length, in a single pass
+    /// (reservoir sampling). Returns `None` if the iterator is empty.
+    ///
+    /// The generator stays locked while the iterator runs, so the iterator
+    /// must not draw from this generator itself; draw from a
+    /// [`stream`](Self::stream) instead.
+    pub fn choose_iter<I: IntoIterator>(&self, items: I) -> Option<I::Item> {
+        self.with_rng(|rng| sampling::choose_iter(rng, items))
+    }
+
+    /// Picks `n` distinct items from an iterator in a single pass
+    /// (reservoir sampling). The result's order is not random.
+    ///
+    /// As with [`choose_iter`](Self::choose_iter), the generator stays locked
+    /// while the iterator runs.
+    pub fn sample_iter<I: IntoIterator>(&self, items: I, n: usize) -> Vec<I::Item> {
+        self.with_rng(|rng| sampling::sample_iter(rng, items, n))
+    }
+
+    /// A point anywhere inside `rect`, evenly spread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::math::{Rect, Vec2};
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::seeded(1);
+    /// let spawn = rng.point_in_rect(Rect::new(-100.0, -50.0, 100.0, 50.0));
+    /// assert!(spawn.x.abs() <= 100.0 && spawn.y.abs() <= 50.0);
+    /// let splash = rng.point_in_circle(Vec2::ZERO, 10.0);
+    /// assert!(splash.length() <= 10.0);
+    /// ```
+    pub fn point_in_rect(&self, rect: Rect) -> Vec2 {
+        self.with_rng(|rng| geometry::point_in_rect(rng, rect))
+    }
+
+    /// A point inside a circle, evenly spread over its area.
+    pub fn point_in_circle(&self, center: Vec2, radius: f32) -> Vec2 {
+        self.with_rng(|rng| geometry::point_in_circle(rng, center, radius))
+    }
+
+    /// A point on the edge of a circle.
+    pub fn point_on_circle(&self, center: Vec2, radius: f32) -> Vec2 {
+        self.with_rng(|rng| geometry::point_on_circle(rng, center, radius))
+    }
+
+    /// A 2D direction, with every angle equally likely.
+    pub fn direction_2d(&self) -> Dir2 {
+        self.with_rng(|rng| geometry::direction_2d(rng))
+    }
+
+    /// A 3D direction, evenly spread over the sphere.
+    pub fn direction_3d(&self) -> Dir3 {
+        self.with_rng(|rng| geometry::direction_3d(rng))
+    }
+
+    /// A rotation, with every orientation equally likely.
+    pub fn rotation(&self) -> Quat {
+        self.with_rng(|rng| geometry::rotation(rng))
+    }
+
+    /// A color whose hue (in degrees), saturation and lightness are drawn
+    /// from the given ranges.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::seeded(1);
+    /// // any shade of green, never grey, black or white
+    /// let leaf = rng.color_hsl(90.0..150.0, 0.5..=1.0, 0.3..0.6);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is empty.
+    pub fn color_hsl(
+        &self,
+        hue: impl SampleRange<f32>,
+        saturation: impl SampleRange<f32>,
+        lightness: impl SampleRange<f32>,
+    ) -> Color {
+        self.with_rng(|rng| geometry::color_hsl(rng, hue, saturation, lightness))
+    }
+
+    /// Scatters points over `rect`, no two closer than `min_distance`, with
+    /// no room left for another (Poisson-disc sampling). Good for placing
+    /// trees, rocks or pickups without clumps or gaps. Returns nothing for
+    /// a rect with no area. The generator stays locked until every point is
+    /// placed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::math::Rect;
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::seeded(1);
+    /// let trees = rng.poisson_disc(Rect::new(0.0, 0.0, 200.0, 200.0), 16.0);
+    /// assert!(trees.len() > 50);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_distance` is not positive and finite, or if it is so
+    /// small for `rect` that the search grid, of cells `min_distance / √2`
+    /// wide, would need more than 2²² (about four million) cells.
+    pub fn poisson_disc(&self, rect: Rect, min_distance: f32) -> Vec<Vec2> {
+        self.with_rng(|rng| geometry::poisson_disc(rng, rect, min_distance))
+    }
+
+    /// Captures the generator's current position and that of every named
+    /// stream. Every lock is held at once, so the snapshot can't catch a
+    /// draw half made.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::seeded(1);
+    /// let saved = rng.snapshot();
+    /// let first: u32 = rng.next();
+    /// rng.restore(&saved);
+    /// assert_eq!(first, rng.next::<u32>());
+    /// ```
+    pub fn snapshot(&self) -> RngSnapshot {
+        let streams = self.streams();
+        let main = lock(&self.main);
+        RngSnapshot {
+            rng: main.rng.clone(),
+            seed: main.seed,
+            streams: streams
+                .iter()
+                .map(|(label, stream)| (label.clone(), lock(&stream.0).rng.clone()))
+                .collect(),
+        }
+    }
+
+    /// Returns the generator to a position captured by [`Self::snapshot`].
+    /// The seed is restored too, so forked streams match the saved generator.
+    ///
+    /// Named streams are moved in place, so [`RngStream`]s already handed out
+    /// follow the restore; one the snapshot doesn't know of goes back to its
+    /// start. Every lock is held at once, so no draw lands between restoring
+    /// the generator and restoring its streams.
+    pub fn restore(&self, snapshot: &RngSnapshot) {
+        let mut streams = self.streams_mut();
+        let mut main = lock(&self.main);
+        main.rng = snapshot.rng.clone();
+        main.seed = snapshot.seed;
+        for label in snapshot.streams.keys() {
+            streams
+                .entry(label.clone())
+                .or_insert_with(|| RngStream(Arc::new(Mutex::new(main.fork(label)))));
+        }
+        // streams are moved in place, so handles already given out follow;
+        // one the snapshot doesn't know of hadn't been created, so starts over
+        for (label, stream) in streams.iter() {
+            let restored = main.fork(label);
+            let mut stream = lock(&stream.0);
+            stream.rng = snapshot.streams.get(label).cloned().unwrap_or(restored.rng);
+            stream.seed = restored.seed;
+        }
+    }
+
+    /// Creates a new generator that continues from a saved position, with
+    /// the named streams the snapshot saved.
+    pub fn from_snapshot(snapshot: &RngSnapshot) -> Self {
+        let rng = Self::with_algorithm(snapshot.rng.algorithm(), snapshot.seed);
+        rng.restore(snapshot);
+        rng
+    }
+
+    /// Starts counting draws and hashing the values drawn, from zero (even
+    /// if the draw log was already running), for desync detection, on the
+    /// generator and on every named stream. See [`DrawLog`].
+    ///
+    /// The log is a debugging aid rather than part of the generator's
+    /// state: snapshots, serialization and forks leave it out, and
+    /// restoring a snapshot doesn't rewind it.
+    pub fn start_draw_log(&self) {
+        let streams = self.streams();
+        lock(&self.main).draw_log = Some(DrawLog::default());
+        for stream in streams.values() {
+            lock(&stream.0).draw_log = Some(DrawLog::default());
+        }
+    }
+
+    /// Stops the draw log started by [`Self::start_draw_log`].
+    pub fn stop_draw_log(&self) {
+        let streams = self.streams();
+        lock(&self.main).draw_log = None;
+        for stream in streams.values() {
+            lock(&stream.0).draw_log = None;
+        }
+    }
+
+    /// Draws made since [`Self::start_draw_log`], from the generator and
+    /// every named stream, and their checksum. Each stream is hashed on its
+    /// own and the streams are combined in label order, so systems drawing
+    /// from separate streams in parallel don't change the checksum.
+    /// `None` while the draw log is off.
+    pub fn draw_log(&self) -> Option<DrawLog> {
+        let streams = self.streams();
+        let mut log = lock(&self.main).draw_log?;
+        for stream in streams.values() {
+            if let Some(stream_log) = lock(&stream.0).draw_log {
+                log.merge(stream_log);
+            }
+        }
+        Some(log)
+    }
+
+    /// Rolls dice written in dice notation, such as `3d6+2` or `4d6kh3`.
+    /// See [`DiceExpr`] for the supported notation.
+    ///
+    /// # Arguments
+    ///
+    /// * `notation` - the dice expression to parse and roll.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomNumberGenerator;
+    /// let rng = RandomNumberGenerator::new();
+    /// let roll = rng.roll_dice("3d6+2").unwrap();
+    /// assert!((5..=20).contains(&roll.total));
+    /// assert_eq!(roll.dice().count(), 3);
+    /// ```
+    pub fn roll_dice(&self, notation: &str) -> Result<DiceRoll, DiceParseError> {
+        let dice: DiceExpr = notation.parse()?;
+        Ok(self.roll(&dice))
+    }
+
+    /// Rolls an already parsed dice expression, holding the generator's lock
+    /// for every die. Parse once with `"3d6".parse::<DiceExpr>()` if you roll
+    /// the same dice repeatedly.
+    pub fn roll(&self, dice: &DiceExpr) -> DiceRoll {
+        // use one stream for the whole expression rather than once per die
+        self.with_rng(|rng| dice.roll_with(|sides| rng.gen_range(1..=sides)))
     }
 }
 
-impl Default for RandomNumberGenerator {
-    fn default() -> Self {
-        Self::new()
+impl rand::RngCore for &RandomNumberGenerator {
+    fn next_u32(&mut self) -> u32 {
+        self.with_rng(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.with_rng(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.with_rng(|rng| rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.with_rng(|rng| rng.try_fill_bytes(dest))
+    }
+}
+
+impl rand::RngCore for RandomNumberGenerator {
+    fn next_u32(&mut self) -> u32 {
+        (&*self).next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (&*self).next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        (&*self).fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        (&*self).try_fill_bytes(dest)
+    }
+}
+
+// keep one stream per dice expression, as the inherent `roll` does
+impl RandomSource for &RandomNumberGenerator {
+    fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        RandomNumberGenerator::roll(self, dice)
+    }
+}
+
+impl RandomSource for RandomNumberGenerator {
+    fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        RandomNumberGenerator::roll(self, dice)
+    }
+}
+
+impl RngStream {
+    fn with_rng<R>(&self, f: impl FnOnce(&mut Tracked<'_, Option<&mut DrawLog>>) -> R) -> R {
+        lock(&self.0).with_rng(f)
+    }
+}
+
+impl rand::RngCore for &RngStream {
+    fn next_u32(&mut self) -> u32 {
+        self.with_rng(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.with_rng(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.with_rng(|rng| rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.with_rng(|rng| rng.try_fill_bytes(dest))
+    }
+}
+
+impl rand::RngCore for RngStream {
+    fn next_u32(&mut self) -> u32 {
+        (&*self).next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (&*self).next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        (&*self).fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        (&*self).try_fill_bytes(dest)
+    }
+}
+
+impl RandomSource for &RngStream {
+    fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        self.with_rng(|rng| dice.roll_with(|sides| rng.gen_range(1..=sides)))
+    }
+}
+
+impl RandomSource for RngStream {
+    fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        (&*self).roll(dice)
+    }
+}
+
+// saved as a snapshot, so named streams are kept too
+#[cfg(feature = "serde")]
+impl serde::Serialize for RandomNumberGenerator {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RandomNumberGenerator {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RngSnapshot::deserialize(deserializer).map(|snapshot| Self::from_snapshot(&snapshot))
     }
 }
 
-impl bevy::prelude::Plugin for RandomPlugin {
-    fn build(&self, app: &mut bevy::prelude::App) {
-        app.insert_resource(RandomNumberGenerator::new());
+impl Default for RandomNumberGenerator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -61,7 +754,7 @@ mod test {
 
     #[test]
     fn test_range_bounds() {
-        let mut rng = RandomNumberGenerator::new();
+        let rng = RandomNumberGenerator::new();
         for _ in 0..1000 {
             let n = rng.range(1..10);
             assert!(n >= 1);
@@ -71,21 +764,41 @@ mod test {
 
     #[test]
     fn test_reproducibility() {
-        let mut rng = (
-            RandomNumberGenerator::seeded(1),
-            RandomNumberGenerator::seeded(1),
-        );
-        (0..1000).for_each(|_| {
-            assert_eq!(
-                rng.0.range(u32::MIN..u32::MAX),
-                rng.1.range(u32::MIN..u32::MAX)
+        for algorithm in Algorithm::ALL {
+            let rng = (
+                RandomNumberGenerator::with_algorithm(algorithm, 1),
+                RandomNumberGenerator::with_algorithm(algorithm, 1),
             );
+            (0..1000).for_each(|_| {
+                assert_eq!(
+                    rng.0.range(u32::MIN..u32::MAX),
+                    rng.1.range(u32::MIN..u32::MAX)
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn test_backend_sequence() {
+        // `seeded` must hand out exactly what the default backend produces
+        let rng = RandomNumberGenerator::seeded(42);
+        assert_eq!(rng.algorithm(), Algorithm::default());
+        let mut backend = AlgorithmRng::seeded(Algorithm::default(), 42);
+        (0..1000).for_each(|_| {
+            assert_eq!(rng.next::<u64>(), backend.r#gen::<u64>());
         });
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 42);
+            let mut backend = AlgorithmRng::seeded(algorithm, 42);
+            (0..1000).for_each(|_| {
+                assert_eq!(rng.next::<u64>(), backend.r#gen::<u64>());
+            });
+        }
     }
 
     #[test]
     fn test_float() {
-        let mut rng = RandomNumberGenerator::new();
+        let rng = RandomNumberGenerator::new();
         for _ in 0..1000 {
             let n = rng.range(-5000.0f32..5000.0f32);
             assert!(n.is_finite());
@@ -96,8 +809,295 @@ mod test {
 
     #[test]
     fn test_next_types() {
-        let mut rng = RandomNumberGenerator::new();
+        let rng = RandomNumberGenerator::new();
         let _: i32 = rng.next();
         let _ = rng.next::<f32>();
     }
+
+    #[test]
+    fn test_roll_dice() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 3);
+            for _ in 0..1000 {
+                let roll = rng.roll_dice("4d6kh3+2").unwrap();
+                assert!((5..=20).contains(&roll.total));
+                assert_eq!(roll.dice().filter(|d| d.kept).count(), 3);
+            }
+            assert!(rng.roll_dice("3d").is_err());
+        }
+    }
+
+    #[test]
+    fn test_roll_reproducibility() {
+        for algorithm in Algorithm::ALL {
+            let dice: DiceExpr = "10d6!+1d20ro1".parse().unwrap();
+            let rng = (
+                RandomNumberGenerator::with_algorithm(algorithm, 7),
+                RandomNumberGenerator::with_algorithm(algorithm, 7),
+            );
+            (0..100).for_each(|_| assert_eq!(rng.0.roll(&dice), rng.1.roll(&dice)));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 9);
+            (0..37).for_each(|_| {
+                rng.next::<u64>();
+            });
+            let saved = rng.snapshot();
+            let expected: Vec<u64> = (0..1000).map(|_| rng.next()).collect();
+
+            rng.restore(&saved);
+            let replayed: Vec<u64> = (0..1000).map(|_| rng.next()).collect();
+            assert_eq!(expected, replayed);
+
+            // a different generator picks up the same sequence
+            let other = RandomNumberGenerator::with_algorithm(algorithm, 10);
+            other.restore(&saved);
+            assert_eq!(expected[0], other.next::<u64>());
+            let fresh = RandomNumberGenerator::from_snapshot(&saved);
+            assert_eq!(expected[0], fresh.next::<u64>());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 11);
+            rng.range(0..100);
+            let saved_rng = ron::to_string(&rng).unwrap();
+            let saved_snapshot = ron::to_string(&rng.snapshot()).unwrap();
+            let expected: Vec<u64> = (0..1000).map(|_| rng.next()).collect();
+
+            let restored: RandomNumberGenerator = ron::from_str(&saved_rng).unwrap();
+            let replayed: Vec<u64> = (0..1000).map(|_| restored.next()).collect();
+            assert_eq!(expected, replayed);
+
+            let snapshot: RngSnapshot = ron::from_str(&saved_snapshot).unwrap();
+            restored.restore(&snapshot);
+            let replayed: Vec<u64> = (0..1000).map(|_| restored.next()).collect();
+            assert_eq!(expected, replayed);
+        }
+    }
+
+    #[test]
+    fn test_fork() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 12);
+            assert_eq!(rng.seed(), 12);
+            let walls = rng.fork("walls");
+            let expected: Vec<u32> = (0..100).map(|_| walls.next()).collect();
+
+            // drawing from the parent or other streams doesn't move a stream
+            (0..100).for_each(|_| {
+                rng.next::<u32>();
+            });
+            let particles = rng.fork("particles");
+            let walls = rng.fork("walls");
+            let replayed: Vec<u32> = (0..100).map(|_| walls.next()).collect();
+            assert_eq!(expected, replayed);
+            let other: Vec<u32> = (0..100).map(|_| particles.next()).collect();
+            assert_ne!(expected, other);
+
+            // a different master seed gives different streams
+            let walls = RandomNumberGenerator::with_algorithm(algorithm, 13).fork("walls");
+            let other: Vec<u32> = (0..100).map(|_| walls.next()).collect();
+            assert_ne!(expected, other);
+
+            // nested forks are deterministic too
+            assert_eq!(
+                rng.fork("ai").fork("pathing").seed(),
+                RandomNumberGenerator::with_algorithm(algorithm, 12)
+                    .fork("ai")
+                    .fork("pathing")
+                    .seed()
+            );
+        }
+    }
+
+    #[test]
+    fn test_algorithm_follows_snapshot() {
+        let rng = RandomNumberGenerator::with_algorithm(Algorithm::XorShift, 20);
+        assert_eq!(rng.fork("walls").algorithm(), Algorithm::XorShift);
+        let saved = RandomNumberGenerator::with_algorithm(Algorithm::ChaCha12, 21).snapshot();
+        rng.restore(&saved);
+        assert_eq!(rng.algorithm(), Algorithm::ChaCha12);
+        assert_eq!(
+            rng.next::<u64>(),
+            AlgorithmRng::seeded(Algorithm::ChaCha12, 21).r#gen::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_snapshot_keeps_seed() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 14);
+            let restored = RandomNumberGenerator::with_algorithm(algorithm, 15);
+            restored.restore(&rng.snapshot());
+            assert_eq!(restored.seed(), 14);
+            assert_eq!(
+                restored.fork("walls").next::<u64>(),
+                rng.fork("walls").next::<u64>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_sequential_threads_share_stream() {
+        // draws come from the one stream, whichever thread makes them
+        let rng = RandomNumberGenerator::seeded(16);
+        let mut replayed: Vec<u64> = Vec::new();
+        for _ in 0..4 {
+            let drawn: Vec<u64> = std::thread::scope(|scope| {
+                scope
+                    .spawn(|| (0..100).map(|_| rng.next()).collect())
+                    .join()
+                    .unwrap()
+            });
+            replayed.extend(drawn);
+        }
+        let mut expected = AlgorithmRng::seeded(Algorithm::default(), 16);
+        let expected: Vec<u64> = (0..400).map(|_| expected.r#gen()).collect();
+        assert_eq!(expected, replayed);
+    }
+
+    #[test]
+    fn test_poisoned_lock() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 17);
+            let mut expected = AlgorithmRng::seeded(algorithm, 17);
+            let saved = rng.snapshot();
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        let _guard = rng.main.lock();
+                        panic!("poison the lock");
+                    })
+                    .join()
+                    .unwrap_err();
+            });
+            assert!(rng.main.is_poisoned());
+            assert_eq!(rng.next::<u64>(), expected.r#gen::<u64>());
+            assert_eq!(rng.snapshot().rng, expected);
+            rng.restore(&saved);
+            assert_eq!(
+                rng.next::<u64>(),
+                AlgorithmRng::seeded(algorithm, 17).r#gen::<u64>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parallel_draws() {
+        let rng = RandomNumberGenerator::seeded(18);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..10_000 {
+                        let n = rng.range(1..=6);
+                        assert!((1..=6).contains(&n));
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_streams() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandomNumberGenerator::with_algorithm(algorithm, 22);
+            let walls = rng.fork("walls");
+            let mut stream = rng.stream("walls");
+            // starts where the fork does, and handles share the stream
+            assert_eq!(stream.next::<u64>(), walls.next::<u64>());
+            assert_eq!(rng.stream("walls").next::<u64>(), walls.next::<u64>());
+            // neither moves the generator's own stream
+            assert_eq!(
+                rng.next::<u64>(),
+                AlgorithmRng::seeded(algorithm, 22).r#gen::<u64>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_stream_snapshot_restore() {
+        let rng = RandomNumberGenerator::seeded(23);
+        let mut walls = rng.stream("walls");
+        walls.next::<u64>();
+        let saved = rng.snapshot();
+        let expected: Vec<u64> = (0..100).map(|_| walls.next()).collect();
+        let mut particles = rng.stream("particles");
+        let fresh: Vec<u64> = (0..100).map(|_| particles.next()).collect();
+
+        // the same seed still rewinds existing handles
+        rng.restore(&saved);
+        let replayed: Vec<u64> = (0..100).map(|_| walls.next()).collect();
+        assert_eq!(expected, replayed);
+        // a stream created after the snapshot starts over
+        let replayed: Vec<u64> = (0..100).map(|_| particles.next()).collect();
+        assert_eq!(fresh, replayed);
+
+        // a new generator gets the saved streams as well
+        let other = RandomNumberGenerator::from_snapshot(&saved);
+        assert_eq!(other.stream("walls").next::<u64>(), expected[0]);
+        assert_eq!(other.seed(), 23);
+    }
+
+    #[test]
+    fn test_stream_draw_log() {
+        let draw = |order: [&str; 2]| {
+            let rng = RandomNumberGenerator::seeded(24);
+            rng.start_draw_log();
+            let streams = order.map(|label| rng.stream(label));
+            std::thread::scope(|scope| {
+                for stream in &streams {
+                    scope.spawn(move || {
+                        for _ in 0..1000 {
+                            let _: u64 = stream.clone().next();
+                        }
+                    });
+                }
+            });
+            rng.next::<u64>();
+            rng.draw_log().unwrap()
+        };
+        // parallel streams give the same log whichever finishes first
+        let log = draw(["walls", "particles"]);
+        assert_eq!(log.draws, 2001);
+        assert_eq!(log, draw(["particles", "walls"]));
+        assert_ne!(log, draw(["walls", "sparks"]));
+    }
+
+    #[test]
+    fn test_restore_keeps_seed_with_state() {
+        let rng = RandomNumberGenerator::seeded(25);
+        let saved = RandomNumberGenerator::with_algorithm(Algorithm::ChaCha12, 26).snapshot();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    rng.restore(&saved);
+                }
+            });
+            for _ in 0..1000 {
+                // a snapshot never pairs one seed with the other state
+                let snapshot = rng.snapshot();
+                assert!(snapshot.seed == 26 || snapshot.rng.algorithm() == Algorithm::default());
+                assert!(snapshot.seed == 25 || snapshot.rng.algorithm() == Algorithm::ChaCha12);
+            }
+        });
+    }
+
+    #[test]
+    fn test_shared_random_source() {
+        fn draw(mut rng: impl RandomSource) -> u64 {
+            rng.next()
+        }
+        let rng = RandomNumberGenerator::seeded(19);
+        let mut expected = AlgorithmRng::seeded(Algorithm::default(), 19);
+        assert_eq!(draw(&rng), expected.r#gen::<u64>());
+        assert_eq!(draw(&rng), expected.r#gen::<u64>());
+    }
 }
@@ -0,0 +1,281 @@
+#[cfg(feature = "locking")]
+use crate::RngStream;
+use crate::{RandomNumberGenerator, log_draw_checksum};
+#[cfg(feature = "locking")]
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// `RandomPlugin` is a Bevy plugin that inserts a [`RandomNumberGenerator`]
+/// resource into your application, along with the [`RandomSeed`] it was
+/// created from. The seed is logged at startup, so a run can be replayed.
+///
+/// Once you add the plugin (with `App::new().add_plugins(RandomPlugin::default())`),
+/// you can access a random number generator in systems with
+/// `rng: ResMut<RandomNumberGenerator>` (or `Res<RandomNumberGenerator>`
+/// with the `locking` feature).
+///
+/// ## Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use my_library::{RandomPlugin, RandomSeed};
+/// let mut app = App::new();
+/// app.add_plugins(RandomPlugin::seeded(42));
+/// assert_eq!(app.world().resource::<RandomSeed>().0, 42);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RandomPlugin {
+    seed: SeedSource,
+    log_draws: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+enum SeedSource {
+    #[default]
+    Entropy,
+    Fixed(u64),
+    Env(String),
+}
+
+/// The seed the [`RandomPlugin`] created the [`RandomNumberGenerator`]
+/// resource with. Put it in bug reports, and pass it to
+/// [`RandomPlugin::seeded`] to replay the session.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RandomSeed(pub u64);
+
+impl RandomPlugin {
+    /// Seeds the generator from entropy, so every run is different.
+    pub fn from_entropy() -> Self {
+        Self::default()
+    }
+
+    /// Seeds the generator with a fixed seed, so every run is the same.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - the random seed to use.
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed: SeedSource::Fixed(seed),
+            ..Self::default()
+        }
+    }
+
+    /// Seeds the generator from an environment variable (e.g. `GAME_SEED=42`)
+    /// when it is set, and from entropy otherwise. A value that isn't a valid
+    /// `u64` is reported with a warning and ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `var` - the name of the environment variable to read at startup.
+    pub fn from_env(var: &str) -> Self {
+        Self {
+            seed: SeedSource::Env(var.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Starts the generator's draw log, and logs its checksum every frame
+    /// with [`log_draw_checksum`], so two runs of the same seed can be
+    /// compared to find where they desync. A debugging aid: each draw costs
+    /// a little more with the log running.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::RandomPlugin;
+    /// let plugin = RandomPlugin::seeded(42).log_draws();
+    /// ```
+    pub fn log_draws(mut self) -> Self {
+        self.log_draws = true;
+        self
+    }
+
+    /// The seed to use, reading environment variables through `lookup`.
+    fn resolve_seed(&self, lookup: impl Fn(&str) -> Option<String>) -> u64 {
+        match &self.seed {
+            SeedSource::Entropy => rand::random(),
+            SeedSource::Fixed(seed) => *seed,
+            SeedSource::Env(var) => match lookup(var) {
+                Some(value) => value.trim().parse().unwrap_or_else(|_| {
+                    warn!("{var}={value:?} is not a valid random seed, ignoring it");
+                    rand::random()
+                }),
+                None => rand::random(),
+            },
+        }
+    }
+}
+
+impl Plugin for RandomPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = self.resolve_seed(|var| std::env::var(var).ok());
+        info!("Random seed: {seed}");
+        app.insert_resource(RandomSeed(seed));
+        #[cfg_attr(feature = "locking", allow(unused_mut))]
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        if self.log_draws {
+            rng.start_draw_log();
+            app.add_systems(Last, log_draw_checksum);
+        }
+        app.insert_resource(rng);
+    }
+}
+
+/// A system's own [`RngStream`]: the [`RandomNumberGenerator`] resource's
+/// named stream `label`, kept between runs so each draw doesn't have to look
+/// it up. Systems drawing from different streams run in parallel without
+/// waiting on each other, and what a system draws depends only on the seed
+/// and on earlier draws from its stream, not on scheduling.
+///
+/// The stream belongs to the resource, so snapshots include it, restoring
+/// one rewinds it and its draws count towards the draw log. Pick a label per
+/// system and keep it when the system is renamed or moved, or its numbers
+/// change; two systems that use the same label share the stream.
+///
+/// ## Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use my_library::{RandomSource, SystemRng};
+/// fn spawn_sparks(mut rng: SystemRng) {
+///     let sparks = rng.get("sparks").range(3..8);
+/// }
+/// ```
+#[cfg(feature = "locking")]
+#[derive(SystemParam)]
+pub struct SystemRng<'w, 's> {
+    shared: Res<'w, RandomNumberGenerator>,
+    stream: Local<'s, Option<(String, RngStream)>>,
+}
+
+#[cfg(feature = "locking")]
+impl SystemRng<'_, '_> {
+    /// The stream `label`, as [`RandomNumberGenerator::stream`] returns it.
+    pub fn get(&mut self, label: &str) -> &mut RngStream {
+        let stream = &mut *self.stream;
+        // a replaced resource has streams of its own
+        if self.shared.is_changed() || !matches!(stream, Some((from, _)) if from == label) {
+            *stream = Some((label.to_string(), self.shared.stream(label)));
+        }
+        &mut stream.as_mut().unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "locking")]
+    use crate::RandomSource;
+
+    fn build(plugin: RandomPlugin) -> App {
+        let mut app = App::new();
+        app.add_plugins(plugin);
+        app
+    }
+
+    fn first_draw(app: &mut App) -> u64 {
+        app.world_mut()
+            .resource_mut::<RandomNumberGenerator>()
+            .next()
+    }
+
+    #[test]
+    fn test_seeded() {
+        let mut app = build(RandomPlugin::seeded(42));
+        assert_eq!(app.world().resource::<RandomSeed>(), &RandomSeed(42));
+        assert_eq!(app.world().resource::<RandomNumberGenerator>().seed(), 42);
+        let mut replay = build(RandomPlugin::seeded(42));
+        assert_eq!(first_draw(&mut app), first_draw(&mut replay));
+    }
+
+    #[test]
+    fn test_log_draws() {
+        let app = build(RandomPlugin::seeded(42));
+        assert_eq!(
+            app.world().resource::<RandomNumberGenerator>().draw_log(),
+            None
+        );
+        let mut app = build(RandomPlugin::seeded(42).log_draws());
+        first_draw(&mut app);
+        app.update();
+        let log = app.world().resource::<RandomNumberGenerator>().draw_log();
+        assert_eq!(log.unwrap().draws, 1);
+    }
+
+    #[test]
+    fn test_entropy_seed_is_recorded() {
+        let app = build(RandomPlugin::default());
+        let seed = app.world().resource::<RandomSeed>().0;
+        assert_eq!(app.world().resource::<RandomNumberGenerator>().seed(), seed);
+    }
+
+    #[cfg(feature = "locking")]
+    fn run_system<M>(app: &mut App, system: impl IntoSystem<(), Vec<u64>, M>) -> Vec<u64> {
+        let world = app.world_mut();
+        let mut system = IntoSystem::into_system(system);
+        system.initialize(world);
+        system.run((), world);
+        system.run((), world)
+    }
+
+    #[cfg(feature = "locking")]
+    fn draw_sparks(mut rng: SystemRng, mut drawn: Local<Vec<u64>>) -> Vec<u64> {
+        drawn.push(rng.get("sparks").next());
+        drawn.clone()
+    }
+
+    #[cfg(feature = "locking")]
+    #[test]
+    fn test_system_rng() {
+        let mut app = build(RandomPlugin::seeded(42));
+        let drawn = run_system(&mut app, draw_sparks);
+        let expected = RandomNumberGenerator::seeded(42).fork("sparks");
+        assert_eq!(drawn, vec![expected.next::<u64>(), expected.next::<u64>()]);
+        // the shared stream wasn't touched
+        assert_eq!(
+            first_draw(&mut app),
+            RandomNumberGenerator::seeded(42).next::<u64>()
+        );
+        // the label picks the stream, not the system
+        let rng = app.world().resource::<RandomNumberGenerator>();
+        assert_eq!(rng.stream("sparks").next::<u64>(), expected.next::<u64>());
+    }
+
+    #[cfg(feature = "locking")]
+    #[test]
+    fn test_system_rng_restore() {
+        let mut app = build(RandomPlugin::seeded(42).log_draws());
+        let saved = app.world().resource::<RandomNumberGenerator>().snapshot();
+        let drawn = run_system(&mut app, draw_sparks);
+        let rng = app.world().resource::<RandomNumberGenerator>();
+        assert_eq!(rng.draw_log().unwrap().draws, 2);
+
+        // the stream is rewound with the resource, even with the same seed
+        let after = rng.snapshot();
+        rng.restore(&saved);
+        assert_eq!(run_system(&mut app, draw_sparks), drawn);
+        let rng = app.world().resource::<RandomNumberGenerator>();
+        assert_eq!(rng.snapshot(), after);
+    }
+
+    #[test]
+    fn test_from_env() {
+        // a fake environment, as setting real variables races other tests
+        let env =
+            |value: &'static str| move |var: &str| (var == "GAME_SEED").then(|| value.to_string());
+        let plugin = RandomPlugin::from_env("GAME_SEED");
+        assert_eq!(plugin.resolve_seed(env(" 1234 ")), 1234);
+        // a bad or missing value falls back to entropy
+        assert_ne!(
+            plugin.resolve_seed(env("not a seed")),
+            plugin.resolve_seed(env("not a seed"))
+        );
+        let plugin = RandomPlugin::from_env("OTHER_SEED");
+        assert_ne!(plugin.resolve_seed(env("1")), plugin.resolve_seed(env("1")));
+
+        let app = build(RandomPlugin::from_env("MY_LIBRARY_TEST_UNSET_SEED"));
+        let seed = app.world().resource::<RandomSeed>().0;
+        assert_eq!(app.world().resource::<RandomNumberGenerator>().seed(), seed);
+    }
+}
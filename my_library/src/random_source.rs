@@ -0,0 +1,278 @@
+use crate::{DiceExpr, DiceParseError, DiceRoll, distributions, geometry, sampling};
+use bevy::{
+    color::Color,
+    math::{Dir2, Dir3, Quat, Rect, Vec2},
+};
+use rand::{
+    Rng,
+    distributions::{
+        Distribution, Standard,
+        uniform::{SampleRange, SampleUniform},
+    },
+};
+use std::ops::Range;
+
+/// `RandomSource` is the random number API shared by every build of
+/// [`RandomNumberGenerator`](crate::RandomNumberGenerator), with or without
+/// the `locking` feature. Write helpers against `impl RandomSource` and they
+/// compile no matter which features Cargo unifies across your workspace.
+///
+/// It is implemented for the generator itself and for references to it
+/// (`&mut RandomNumberGenerator`, and with `locking` also
+/// `&RandomNumberGenerator` and its named `RngStream`s), so pass `&mut rng`,
+/// or `&*rng` from a `Res<RandomNumberGenerator>`. Every implementor is also a
+/// [`rand::RngCore`], so the rest of the `rand` ecosystem accepts it too.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{RandomNumberGenerator, RandomSource};
+///
+/// fn coin_flip(mut rng: impl RandomSource) -> bool {
+///     rng.range(0..2) == 0
+/// }
+///
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let _heads = coin_flip(&mut rng);
+/// ```
+pub trait RandomSource: rand::RngCore {
+    /// Generates a new random number of the requested type.
+    fn next<T>(&mut self) -> T
+    where
+        Standard: Distribution<T>,
+    {
+        self.r#gen()
+    }
+
+    /// Generates a random number within the specified range.
+    fn range<T>(&mut self, range: impl SampleRange<T>) -> T
+    where
+        T: SampleUniform + PartialOrd,
+    {
+        self.gen_range(range)
+    }
+
+    /// Samples a normal (gaussian) distribution: values near `mean` are
+    /// most likely, and about 68% fall within `std_dev` of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `std_dev` is negative or not finite.
+    fn gaussian(&mut self, mean: f32, std_dev: f32) -> f32 {
+        distributions::gaussian(self, mean, std_dev)
+    }
+
+    /// Like [`gaussian`](Self::gaussian), but clamped to `min..=max` so the
+    /// rare outlier can't break gameplay.
+    fn gaussian_clamped(&mut self, mean: f32, std_dev: f32, min: f32, max: f32) -> f32 {
+        distributions::gaussian_clamped(self, mean, std_dev, min, max)
+    }
+
+    /// Samples an exponential distribution with the given mean: the time
+    /// between events that happen at random at a steady rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mean` is not positive.
+    fn exponential(&mut self, mean: f32) -> f32 {
+        distributions::exponential(self, mean)
+    }
+
+    /// Samples a Poisson distribution with the given mean: how many events
+    /// happen in an interval, if they happen `mean` times on average.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mean` is not positive and finite.
+    fn poisson(&mut self, mean: f32) -> u32 {
+        distributions::poisson(self, mean)
+    }
+
+    /// Samples a triangular distribution between `min` and `max`, most
+    /// likely to be near `mode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `min <= mode <= max`.
+    fn triangular(&mut self, min: f32, max: f32, mode: f32) -> f32 {
+        distributions::triangular(self, min, max, mode)
+    }
+
+    /// Averages `rolls` uniform draws from `range`, like averaging `NdM`
+    /// dice: more rolls give a tighter bell curve around the middle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rolls` is zero or `range` is empty.
+    fn bell_curve(&mut self, range: Range<f32>, rolls: u32) -> f32 {
+        distributions::bell_curve(self, range, rolls)
+    }
+
+    /// Shuffles `items` in place.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        sampling::shuffle(self, items)
+    }
+
+    /// Picks one item, or `None` if `items` is empty.
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        sampling::choose(self, items)
+    }
+
+    /// Picks one item, with a chance proportional to its `weight`. Returns
+    /// `None` if `items` is empty, the weights add up to zero, or a weight
+    /// is negative.
+    fn choose_weighted<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> f32,
+    ) -> Option<&'a T> {
+        sampling::choose_weighted(self, items, weight)
+    }
+
+    /// Picks `n` distinct items (or all of them, if there are fewer),
+    /// in random order.
+    fn sample<'a, T>(&mut self, items: &'a [T], n: usize) -> Vec<&'a T> {
+        sampling::sample(self, items, n)
+    }
+
+    /// Picks one item from an iterator of unknown length, in a single pass
+    /// (reservoir sampling). Returns `None` if the iterator is empty.
+    fn choose_iter<I: IntoIterator>(&mut self, items: I) -> Option<I::Item> {
+        sampling::choose_iter(self, items)
+    }
+
+    /// Picks `n` distinct items from an iterator in a single pass
+    /// (reservoir sampling). The result's order is not random.
+    fn sample_iter<I: IntoIterator>(&mut self, items: I, n: usize) -> Vec<I::Item> {
+        sampling::sample_iter(self, items, n)
+    }
+
+    /// A point anywhere inside `rect`, evenly spread.
+    fn point_in_rect(&mut self, rect: Rect) -> Vec2 {
+        geometry::point_in_rect(self, rect)
+    }
+
+    /// A point inside a circle, evenly spread over its area.
+    fn point_in_circle(&mut self, center: Vec2, radius: f32) -> Vec2 {
+        geometry::point_in_circle(self, center, radius)
+    }
+
+    /// A point on the edge of a circle.
+    fn point_on_circle(&mut self, center: Vec2, radius: f32) -> Vec2 {
+        geometry::point_on_circle(self, center, radius)
+    }
+
+    /// A 2D direction, with every angle equally likely.
+    fn direction_2d(&mut self) -> Dir2 {
+        geometry::direction_2d(self)
+    }
+
+    /// A 3D direction, evenly spread over the sphere.
+    fn direction_3d(&mut self) -> Dir3 {
+        geometry::direction_3d(self)
+    }
+
+    /// A rotation, with every orientation equally likely.
+    fn rotation(&mut self) -> Quat {
+        geometry::rotation(self)
+    }
+
+    /// A color whose hue (in degrees), saturation and lightness are drawn
+    /// from the given ranges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is empty.
+    fn color_hsl(
+        &mut self,
+        hue: impl SampleRange<f32>,
+        saturation: impl SampleRange<f32>,
+        lightness: impl SampleRange<f32>,
+    ) -> Color {
+        geometry::color_hsl(self, hue, saturation, lightness)
+    }
+
+    /// Scatters points over `rect`, no two closer than `min_distance`, with
+    /// no room left for another (Poisson-disc sampling). Returns nothing
+    /// for a rect with no area.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_distance` is not positive and finite, or if it is so
+    /// small for `rect` that the search grid, of cells `min_distance / √2`
+    /// wide, would need more than 2²² (about four million) cells.
+    fn poisson_disc(&mut self, rect: Rect, min_distance: f32) -> Vec<Vec2> {
+        geometry::poisson_disc(self, rect, min_distance)
+    }
+
+    /// Rolls dice written in dice notation, such as `3d6+2` or `4d6kh3`.
+    /// See [`DiceExpr`] for the supported notation.
+    fn roll_dice(&mut self, notation: &str) -> Result<DiceRoll, DiceParseError> {
+        let dice: DiceExpr = notation.parse()?;
+        Ok(self.roll(&dice))
+    }
+
+    /// Rolls an already parsed dice expression.
+    fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        dice.roll_with(|sides| self.gen_range(1..=sides))
+    }
+}
+
+impl<R: RandomSource + ?Sized> RandomSource for &mut R {
+    fn next<T>(&mut self) -> T
+    where
+        Standard: Distribution<T>,
+    {
+        (**self).next()
+    }
+
+    fn range<T>(&mut self, range: impl SampleRange<T>) -> T
+    where
+        T: SampleUniform + PartialOrd,
+    {
+        (**self).range(range)
+    }
+
+    fn roll(&mut self, dice: &DiceExpr) -> DiceRoll {
+        (**self).roll(dice)
+    }
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(clippy::unnecessary_mut_passed))]
+mod test {
+    use super::*;
+    use crate::RandomNumberGenerator;
+    use rand::seq::SliceRandom;
+
+    fn draw(mut rng: impl RandomSource) -> (u64, i32, i64) {
+        let roll = rng.roll_dice("3d6+1").unwrap().total;
+        (rng.next(), rng.range(1..=6), roll)
+    }
+
+    #[test]
+    fn test_generic_matches_inherent() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let generic = draw(&mut rng);
+        #[allow(unused_mut)]
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let roll = rng.roll_dice("3d6+1").unwrap().total;
+        let inherent = (rng.next::<u64>(), rng.range(1..=6), roll);
+        assert_eq!(generic, inherent);
+    }
+
+    #[test]
+    fn test_rand_ecosystem() {
+        let mut rng = (
+            RandomNumberGenerator::seeded(2),
+            RandomNumberGenerator::seeded(2),
+        );
+        let mut deck: Vec<u32> = (0..52).collect();
+        let mut other = deck.clone();
+        deck.shuffle(&mut rng.0);
+        other.shuffle(&mut rng.1);
+        assert_eq!(deck, other);
+        assert_ne!(deck, (0..52).collect::<Vec<u32>>());
+    }
+}
@@ -0,0 +1,290 @@
+//! Weighted random tables, for loot drops, spawn lists and the like.
+use crate::RandomSource;
+
+/// An entry in a [`RandomTable`]: either an item, or a nested table that is
+/// rolled in turn when selected.
+#[derive(Clone, Debug)]
+pub enum TableEntry<T> {
+    Item(T),
+    Table(RandomTable<T>),
+}
+
+/// A value in `0..total`, drawn as a `u32` when `total` fits. Tables whose
+/// total weight fits in a `u32` draw exactly what `rng.range(0..total)` on
+/// `u32`s would; larger totals draw a `u64`.
+fn draw(rng: &mut impl RandomSource, total: u64) -> u64 {
+    match u32::try_from(total) {
+        Ok(total) => rng.range(0..total).into(),
+        Err(_) => rng.range(0..total),
+    }
+}
+
+/// `RandomTable` holds weighted entries and draws from them with a
+/// [`RandomNumberGenerator`](crate::RandomNumberGenerator). An entry with
+/// weight 3 is three times as likely to be drawn as an entry with weight 1.
+///
+/// Entries may themselves be tables: selecting a nested table rolls on it,
+/// so "25% chance of a weapon, then pick which weapon" is expressed directly.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{RandomNumberGenerator, RandomTable};
+/// let weapons = RandomTable::new().add("sword", 2).add("axe", 1);
+/// let loot = RandomTable::new()
+///     .add("gold", 6)
+///     .add("potion", 2)
+///     .add_table(weapons, 2);
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let drop = loot.roll(&mut rng).unwrap();
+/// assert!(["gold", "potion", "sword", "axe"].contains(drop));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RandomTable<T> {
+    entries: Vec<(u32, TableEntry<T>)>,
+}
+
+impl<T> Default for RandomTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> RandomTable<T> {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds an item with the given weight. Zero-weight items are never drawn.
+    pub fn add(mut self, item: T, weight: u32) -> Self {
+        self.entries.push((weight, TableEntry::Item(item)));
+        self
+    }
+
+    /// Adds a nested table with the given weight. When selected, the nested
+    /// table is rolled to pick the final item.
+    pub fn add_table(mut self, table: RandomTable<T>, weight: u32) -> Self {
+        self.entries.push((weight, TableEntry::Table(table)));
+        self
+    }
+
+    /// The entries of this table, with their weights.
+    pub fn entries(&self) -> &[(u32, TableEntry<T>)] {
+        &self.entries
+    }
+
+    /// `true` if nothing can be drawn from this table.
+    pub fn is_empty(&self) -> bool {
+        self.entries.iter().all(|(weight, entry)| {
+            *weight == 0
+                || match entry {
+                    TableEntry::Item(_) => false,
+                    TableEntry::Table(table) => table.is_empty(),
+                }
+        })
+    }
+
+    /// Draws one item. Returns `None` if the table is empty.
+    pub fn roll(&self, mut rng: impl RandomSource) -> Option<&T> {
+        self.pick(&mut |total| draw(&mut rng, total), &[], 0)
+            .map(|(_, item)| item)
+    }
+
+    /// Draws `n` items *with* replacement: the same item can come up
+    /// more than once. Returns an empty list if the table is empty.
+    pub fn roll_many(&self, mut rng: impl RandomSource, n: usize) -> Vec<&T> {
+        let mut roll = |total| draw(&mut rng, total);
+        (0..n)
+            .map_while(|_| self.pick(&mut roll, &[], 0).map(|(_, item)| item))
+            .collect()
+    }
+
+    /// Draws up to `n` distinct items *without* replacement. Once an item
+    /// is drawn it is removed (and a nested table that runs out of items
+    /// drops out too), so fewer than `n` items are returned if the table
+    /// runs dry.
+    pub fn roll_unique(&self, mut rng: impl RandomSource, n: usize) -> Vec<&T> {
+        let mut roll = |total| draw(&mut rng, total);
+        let mut taken = vec![false; self.leaf_count()];
+        let mut result = Vec::with_capacity(n);
+        while result.len() < n {
+            let Some((leaf, item)) = self.pick(&mut roll, &taken, 0) else {
+                break;
+            };
+            taken[leaf] = true;
+            result.push(item);
+        }
+        result
+    }
+
+    /// Number of items in this table and all nested tables.
+    fn leaf_count(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(_, entry)| match entry {
+                TableEntry::Item(_) => 1,
+                TableEntry::Table(table) => table.leaf_count(),
+            })
+            .sum()
+    }
+
+    /// `true` if at least one item below this table can still be drawn.
+    /// Items are numbered depth-first from `first_leaf`.
+    fn available(&self, taken: &[bool], first_leaf: usize) -> bool {
+        let mut leaf = first_leaf;
+        self.entries.iter().any(|(weight, entry)| {
+            let (size, open) = Self::entry_state(entry, taken, leaf);
+            leaf += size;
+            *weight > 0 && open
+        })
+    }
+
+    fn entry_state(entry: &TableEntry<T>, taken: &[bool], leaf: usize) -> (usize, bool) {
+        match entry {
+            TableEntry::Item(_) => (1, !taken.get(leaf).copied().unwrap_or(false)),
+            TableEntry::Table(table) => (table.leaf_count(), table.available(taken, leaf)),
+        }
+    }
+
+    /// Picks an item that is not marked in `taken` (an empty slice marks
+    /// nothing), returning its depth-first index along with it. `roll(n)`
+    /// must return a value in `0..n`; weights are summed as `u64`, so no
+    /// number of `u32` weights can overflow.
+    fn pick(
+        &self,
+        roll: &mut impl FnMut(u64) -> u64,
+        taken: &[bool],
+        first_leaf: usize,
+    ) -> Option<(usize, &T)> {
+        let mut candidates = Vec::with_capacity(self.entries.len());
+        let mut leaf = first_leaf;
+        for (weight, entry) in self.entries.iter() {
+            let (size, open) = Self::entry_state(entry, taken, leaf);
+            if *weight > 0 && open {
+                candidates.push((u64::from(*weight), entry, leaf));
+            }
+            leaf += size;
+        }
+        let total: u64 = candidates.iter().map(|(weight, _, _)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut target = roll(total);
+        for (weight, entry, leaf) in candidates {
+            if target < weight {
+                return match entry {
+                    TableEntry::Item(item) => Some((leaf, item)),
+                    TableEntry::Table(table) => table.pick(roll, taken, leaf),
+                };
+            }
+            target -= weight;
+        }
+        unreachable!("roll returned a value outside 0..total")
+    }
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(clippy::unnecessary_mut_passed))]
+mod test {
+    use super::*;
+    use crate::RandomNumberGenerator;
+
+    const DRAWS: usize = 100_000;
+
+    fn frequency<T: PartialEq>(draws: &[&T], item: T) -> f64 {
+        draws.iter().filter(|d| ***d == item).count() as f64 / draws.len() as f64
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let table: RandomTable<i32> = RandomTable::new().add(1, 0);
+        assert!(table.is_empty());
+        assert_eq!(table.roll(&mut rng), None);
+        assert!(table.roll_many(&mut rng, 5).is_empty());
+        let nested: RandomTable<i32> = RandomTable::new().add_table(RandomTable::new(), 5);
+        assert!(nested.is_empty());
+        assert_eq!(nested.roll(&mut rng), None);
+    }
+
+    #[test]
+    fn test_weighted_distribution() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let table = RandomTable::new().add('a', 1).add('b', 3).add('c', 0);
+        let draws = table.roll_many(&mut rng, DRAWS);
+        assert_eq!(draws.len(), DRAWS);
+        assert!((frequency(&draws, 'a') - 0.25).abs() < 0.01);
+        assert!((frequency(&draws, 'b') - 0.75).abs() < 0.01);
+        assert_eq!(frequency(&draws, 'c'), 0.0);
+    }
+
+    #[test]
+    fn test_huge_weights() {
+        let mut rng = RandomNumberGenerator::seeded(6);
+        let table = RandomTable::new()
+            .add('a', u32::MAX)
+            .add('b', u32::MAX)
+            .add('c', 2 * (u32::MAX / 3));
+        let draws = table.roll_many(&mut rng, DRAWS);
+        assert!((frequency(&draws, 'a') - 0.375).abs() < 0.01);
+        assert!((frequency(&draws, 'c') - 0.25).abs() < 0.01);
+        assert_eq!(table.roll_unique(&mut rng, 5).len(), 3);
+    }
+
+    #[test]
+    fn test_nested_distribution() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let weapons = RandomTable::new().add("sword", 1).add("axe", 1);
+        let table = RandomTable::new().add("gold", 1).add_table(weapons, 1);
+        let draws = table.roll_many(&mut rng, DRAWS);
+        assert!((frequency(&draws, "gold") - 0.5).abs() < 0.01);
+        assert!((frequency(&draws, "sword") - 0.25).abs() < 0.01);
+        assert!((frequency(&draws, "axe") - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_unique() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let weapons = RandomTable::new().add(3, 1).add(4, 1);
+        let table = RandomTable::new()
+            .add(1, 10)
+            .add(2, 1)
+            .add_table(weapons, 5)
+            .add(5, 0);
+        for _ in 0..1000 {
+            let mut draws = table.roll_unique(&mut rng, 10);
+            draws.sort();
+            assert_eq!(draws, vec![&1, &2, &3, &4]);
+        }
+        assert_eq!(table.roll_unique(&mut rng, 2).len(), 2);
+    }
+
+    #[test]
+    fn test_unique_distribution() {
+        // With weights 1 and 3, the first of two unique draws follows the
+        // weights; the second is whatever is left
+        let mut rng = RandomNumberGenerator::seeded(4);
+        let table = RandomTable::new().add('a', 1).add('b', 3);
+        let firsts: Vec<&char> = (0..DRAWS)
+            .map(|_| table.roll_unique(&mut rng, 2)[0])
+            .collect();
+        assert!((frequency(&firsts, 'a') - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_reproducibility() {
+        let table = RandomTable::new().add(1, 5).add(2, 3).add(3, 1);
+        let mut rng = (
+            RandomNumberGenerator::seeded(5),
+            RandomNumberGenerator::seeded(5),
+        );
+        assert_eq!(
+            table.roll_many(&mut rng.0, 1000),
+            table.roll_many(&mut rng.1, 1000)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Shuffling and picking from collections, shared by every build of
+//! [`RandomNumberGenerator`](crate::RandomNumberGenerator) and by
+//! [`RandomSource`](crate::RandomSource).
+use rand::{
+    Rng,
+    seq::{IteratorRandom, SliceRandom},
+};
+
+pub(crate) fn shuffle<R: Rng + ?Sized, T>(rng: &mut R, items: &mut [T]) {
+    items.shuffle(rng);
+}
+
+pub(crate) fn choose<'a, R: Rng + ?Sized, T>(rng: &mut R, items: &'a [T]) -> Option<&'a T> {
+    items.choose(rng)
+}
+
+pub(crate) fn choose_weighted<'a, R: Rng + ?Sized, T>(
+    rng: &mut R,
+    items: &'a [T],
+    weight: impl Fn(&T) -> f32,
+) -> Option<&'a T> {
+    // empty, all-zero and invalid weights alike leave nothing to choose
+    items.choose_weighted(rng, weight).ok()
+}
+
+pub(crate) fn sample<'a, R: Rng + ?Sized, T>(rng: &mut R, items: &'a [T], n: usize) -> Vec<&'a T> {
+    items.choose_multiple(rng, n).collect()
+}
+
+pub(crate) fn choose_iter<R: Rng + ?Sized, I: IntoIterator>(
+    rng: &mut R,
+    items: I,
+) -> Option<I::Item> {
+    items.into_iter().choose(rng)
+}
+
+pub(crate) fn sample_iter<R: Rng + ?Sized, I: IntoIterator>(
+    rng: &mut R,
+    items: I,
+    n: usize,
+) -> Vec<I::Item> {
+    items.into_iter().choose_multiple(rng, n)
+}
+
+#[cfg(test)]
+// inherent methods take `&mut self` only without the `locking` feature
+#[cfg_attr(feature = "locking", allow(unused_mut, clippy::unnecessary_mut_passed))]
+mod test {
+    use crate::{Algorithm, RandomNumberGenerator, RandomSource};
+
+    const DRAWS: usize = 100_000;
+
+    fn assert_frequency(count: usize, expected: f64) {
+        let frequency = count as f64 / DRAWS as f64;
+        assert!(
+            (frequency - expected).abs() < 0.01,
+            "frequency {frequency} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn test_shuffle() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = RandomNumberGenerator::with_algorithm(algorithm, 1);
+            let mut deck: Vec<u32> = (0..52).collect();
+            rng.shuffle(&mut deck);
+            assert_ne!(deck, (0..52).collect::<Vec<u32>>());
+            deck.sort();
+            assert_eq!(deck, (0..52).collect::<Vec<u32>>());
+        }
+    }
+
+    #[test]
+    fn test_choose() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let empty: [u32; 0] = [];
+        assert_eq!(rng.choose(&empty), None);
+        let items = [1, 2, 3, 4];
+        let ones = (0..DRAWS)
+            .filter(|_| rng.choose(&items) == Some(&1))
+            .count();
+        assert_frequency(ones, 0.25);
+    }
+
+    #[test]
+    fn test_choose_weighted() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let items = [("common", 3.0), ("rare", 1.0), ("never", 0.0)];
+        let mut counts = [0; 3];
+        for _ in 0..DRAWS {
+            let (name, _) = rng.choose_weighted(&items, |(_, w)| *w).unwrap();
+            counts[items.iter().position(|(n, _)| n == name).unwrap()] += 1;
+        }
+        assert_frequency(counts[0], 0.75);
+        assert_frequency(counts[1], 0.25);
+        assert_eq!(counts[2], 0);
+
+        assert_eq!(rng.choose_weighted(&items[2..], |(_, w)| *w), None);
+        assert_eq!(rng.choose_weighted(&[1.0, -1.0], |w| *w), None);
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut rng = RandomNumberGenerator::seeded(4);
+        let items: Vec<u32> = (0..10).collect();
+        for _ in 0..100 {
+            let mut drawn = rng.sample(&items, 5);
+            assert_eq!(drawn.len(), 5);
+            drawn.sort();
+            drawn.dedup();
+            assert_eq!(drawn.len(), 5);
+        }
+        assert_eq!(rng.sample(&items, 20).len(), 10);
+    }
+
+    #[test]
+    fn test_reservoir() {
+        let mut rng = RandomNumberGenerator::seeded(5);
+        assert_eq!(rng.choose_iter(std::iter::empty::<u32>()), None);
+        let threes = (0..DRAWS)
+            .filter(|_| rng.choose_iter((0..10).filter(|n| n % 2 == 1)) == Some(3))
+            .count();
+        assert_frequency(threes, 0.2);
+
+        let drawn = rng.sample_iter(0..1000, 10);
+        assert_eq!(drawn.len(), 10);
+        assert!(drawn.iter().all(|n| *n < 1000));
+        assert_eq!(rng.sample_iter(0..3, 10).len(), 3);
+    }
+
+    #[test]
+    fn test_reproducibility() {
+        for algorithm in Algorithm::ALL {
+            let mut rng = (
+                RandomNumberGenerator::with_algorithm(algorithm, 6),
+                RandomNumberGenerator::with_algorithm(algorithm, 6),
+            );
+            let items: Vec<u32> = (0..100).collect();
+            let mut decks = (items.clone(), items.clone());
+            rng.0.shuffle(&mut decks.0);
+            rng.1.shuffle(&mut decks.1);
+            assert_eq!(decks.0, decks.1);
+            assert_eq!(rng.0.sample(&items, 10), rng.1.sample(&items, 10));
+            assert_eq!(rng.0.sample_iter(0..100, 10), rng.1.sample_iter(0..100, 10));
+        }
+    }
+
+    #[test]
+    fn test_random_source() {
+        fn pick(mut rng: impl RandomSource, items: &[u32]) -> u32 {
+            *rng.choose(items).unwrap()
+        }
+        let mut rng = (
+            RandomNumberGenerator::seeded(7),
+            RandomNumberGenerator::seeded(7),
+        );
+        let items: Vec<u32> = (0..100).collect();
+        assert_eq!(pick(&mut rng.0, &items), *rng.1.choose(&items).unwrap());
+    }
+}
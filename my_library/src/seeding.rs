@@ -0,0 +1,43 @@
+//! Stable seed derivation for [`RandomNumberGenerator::fork`](crate::RandomNumberGenerator::fork).
+//!
+//! `std`'s hashers are not guaranteed to stay the same between Rust releases,
+//! so forked streams use FNV-1a and SplitMix64, which never change.
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// One round of SplitMix64: spreads similar inputs far apart.
+pub(crate) fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Derives the seed of the child stream `label` from a parent seed.
+pub(crate) fn derive_seed(seed: u64, label: &str) -> u64 {
+    splitmix64(seed ^ splitmix64(fnv1a(label.as_bytes())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_derive_seed_is_stable() {
+        // Pinned so a change to the derivation (which would silently change
+        // every forked stream) fails loudly
+        assert_eq!(fnv1a(b""), FNV_OFFSET);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(splitmix64(0), 0xe220_a839_7b1d_cdaf);
+        assert_ne!(derive_seed(1, "walls"), derive_seed(1, "particles"));
+        assert_ne!(derive_seed(1, "walls"), derive_seed(2, "walls"));
+        assert_eq!(derive_seed(1, "walls"), derive_seed(1, "walls"));
+    }
+}
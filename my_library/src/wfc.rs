@@ -0,0 +1,951 @@
+//! Wave function collapse: fill a grid with tiles so that every pair of
+//! neighbors obeys a set of adjacency rules, learned from an example grid or
+//! read from a rules file.
+use crate::{GridMap, MapTile, RandomSource};
+use bevy::{
+    log::debug,
+    math::IVec2,
+    prelude::{Component, Resource},
+};
+use std::{cmp::Reverse, collections::BinaryHeap, fmt, path::Path};
+
+/// The most distinct tiles a rule set can hold: one bit each in a [`Mask`].
+const MAX_TILES: usize = 128;
+
+/// The tiles a cell could still become, one bit per tile.
+type Mask = u128;
+
+/// How many times [`WaveFunctionCollapse::generate`] tries before giving
+/// up, starting over whenever an attempt runs out of backtracks.
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Right, left, down and up. Row 0 is the top row, as [`TileGrid`] prints.
+const DIRECTIONS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+const RIGHT: usize = 0;
+const LEFT: usize = 1;
+const DOWN: usize = 2;
+const UP: usize = 3;
+
+/// `WfcRules` lists the tiles a [`WaveFunctionCollapse`] can place, how
+/// often each should appear, and which tiles may sit next to which. Each
+/// tile is a single character, so rules and results can be written as
+/// ASCII art.
+///
+/// Rules are directional: allowing `b` to the right of `a` says nothing
+/// about `a` to the right of `b`.
+///
+/// Build rules in code, learn them from an example with
+/// [`WfcRules::from_example`], or load them from text with
+/// [`WfcRules::parse`] and [`WfcRules::from_file`]. A rules file has one
+/// directive per line; blank lines and lines starting with `#` are skipped:
+///
+/// ```text
+/// # tile <symbol> <weight>: how common a tile is (1 if never given)
+/// tile . 8
+/// tile ~ 2
+/// # h <left> <right>: <right> may sit to the right of <left>
+/// h . .
+/// h . ~
+/// h ~ .
+/// # v <top> <bottom>: <bottom> may sit below <top>
+/// v . .
+/// v . ~
+/// v ~ .
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WfcRules {
+    symbols: Vec<char>,
+    weights: Vec<f32>,
+    // allowed[direction][tile]: the tiles that may sit in `direction` of `tile`
+    allowed: [Vec<Mask>; 4],
+}
+
+/// An error in a [`WfcRules`] file or example grid.
+#[derive(Debug)]
+pub enum WfcRulesError {
+    /// The rules file could not be read.
+    Io(std::io::Error),
+    /// A line that isn't `tile`, `h` or `v`.
+    UnknownDirective { line: usize, found: String },
+    /// A tile symbol that isn't exactly one character, or is missing.
+    BadSymbol { line: usize, found: String },
+    /// A weight that isn't a positive number.
+    BadWeight { line: usize, found: String },
+    /// A line with more fields than its directive takes.
+    TrailingText { line: usize },
+    /// More distinct tiles than are supported (128).
+    TooManyTiles { line: usize },
+    /// The rules or example have no tiles at all.
+    NoTiles,
+}
+
+impl fmt::Display for WfcRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read rules: {err}"),
+            Self::UnknownDirective { line, found } => {
+                write!(f, "line {line}: unknown directive {found:?}")
+            }
+            Self::BadSymbol { line, found } => {
+                write!(f, "line {line}: {found:?} is not a single-character tile")
+            }
+            Self::BadWeight { line, found } => {
+                write!(f, "line {line}: {found:?} is not a positive weight")
+            }
+            Self::TrailingText { line } => write!(f, "line {line}: unexpected text at the end"),
+            Self::TooManyTiles { line } => {
+                write!(f, "line {line}: more than {MAX_TILES} different tiles")
+            }
+            Self::NoTiles => write!(f, "no tiles defined"),
+        }
+    }
+}
+
+impl std::error::Error for WfcRulesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl WfcRules {
+    /// Creates an empty rule set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a tile, or changes its weight. Tiles appear in proportion to
+    /// their weights, as far as the adjacency rules allow.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is not positive and finite, or this is tile 129.
+    pub fn tile(mut self, symbol: char, weight: f32) -> Self {
+        assert!(
+            weight > 0.0 && weight.is_finite(),
+            "WfcRules: weight must be positive and finite"
+        );
+        let tile = self.add_or_panic(symbol);
+        self.weights[tile] = weight;
+        self
+    }
+
+    /// Allows `right` to sit directly to the right of `left`. Unknown tiles
+    /// are added with weight 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this adds tile 129.
+    pub fn horizontal(mut self, left: char, right: char) -> Self {
+        let (left, right) = (self.add_or_panic(left), self.add_or_panic(right));
+        self.allow(left, right, RIGHT, LEFT);
+        self
+    }
+
+    /// Allows `bottom` to sit directly below `top`. Unknown tiles are added
+    /// with weight 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this adds tile 129.
+    pub fn vertical(mut self, top: char, bottom: char) -> Self {
+        let (top, bottom) = (self.add_or_panic(top), self.add_or_panic(bottom));
+        self.allow(top, bottom, DOWN, UP);
+        self
+    }
+
+    /// Learns rules from an example grid, one row per line: every pair of
+    /// neighbors in the example is allowed, and each tile's weight is how
+    /// often it appears. Blank lines are skipped, and rows may differ in
+    /// length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use my_library::WfcRules;
+    /// let rules = WfcRules::from_example(
+    ///     "
+    ///     ~~~~~~
+    ///     ~~..~~
+    ///     ~.^^.~
+    ///     ~~..~~
+    ///     ",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(rules.symbols(), ['~', '.', '^']);
+    /// ```
+    pub fn from_example(example: &str) -> Result<Self, WfcRulesError> {
+        let mut rules = Self::new();
+        let rows: Vec<(usize, Vec<char>)> = example
+            .lines()
+            .enumerate()
+            .map(|(number, row)| (number + 1, row.trim().chars().collect()))
+            .filter(|(_, row): &(usize, Vec<char>)| !row.is_empty())
+            .collect();
+        let mut seen = vec![];
+        for (line, row) in &rows {
+            let mut tiles = Vec::with_capacity(row.len());
+            for symbol in row {
+                let tile = rules
+                    .add(*symbol)
+                    .ok_or(WfcRulesError::TooManyTiles { line: *line })?;
+                seen.resize(rules.symbols.len(), 0u32);
+                seen[tile] += 1;
+                tiles.push(tile);
+            }
+            for pair in tiles.windows(2) {
+                rules.allow(pair[0], pair[1], RIGHT, LEFT);
+            }
+        }
+        for pair in rows.windows(2) {
+            for (top, bottom) in pair[0].1.iter().zip(&pair[1].1) {
+                let (top, bottom) = (rules.index(*top).unwrap(), rules.index(*bottom).unwrap());
+                rules.allow(top, bottom, DOWN, UP);
+            }
+        }
+        if rules.symbols.is_empty() {
+            return Err(WfcRulesError::NoTiles);
+        }
+        rules.weights = seen.into_iter().map(|count| count as f32).collect();
+        Ok(rules)
+    }
+
+    /// Reads rules in the format described on [`WfcRules`].
+    pub fn parse(text: &str) -> Result<Self, WfcRulesError> {
+        let mut rules = Self::new();
+        for (number, text) in text.lines().enumerate() {
+            let line = number + 1;
+            let mut fields = text.split_whitespace();
+            let Some(directive) = fields.next().filter(|field| !field.starts_with('#')) else {
+                continue;
+            };
+            let mut symbol = || {
+                let field = fields.next().unwrap_or_default();
+                let mut chars = field.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(symbol), None) => rules
+                        .add(symbol)
+                        .ok_or(WfcRulesError::TooManyTiles { line }),
+                    _ => Err(WfcRulesError::BadSymbol {
+                        line,
+                        found: field.to_string(),
+                    }),
+                }
+            };
+            match directive {
+                "tile" => {
+                    let tile = symbol()?;
+                    let weight = match fields.next() {
+                        None => 1.0,
+                        Some(field) => field
+                            .parse()
+                            .ok()
+                            .filter(|weight: &f32| *weight > 0.0 && weight.is_finite())
+                            .ok_or_else(|| WfcRulesError::BadWeight {
+                                line,
+                                found: field.to_string(),
+                            })?,
+                    };
+                    rules.weights[tile] = weight;
+                }
+                "h" => {
+                    let (left, right) = (symbol()?, symbol()?);
+                    rules.allow(left, right, RIGHT, LEFT);
+                }
+                "v" => {
+                    let (top, bottom) = (symbol()?, symbol()?);
+                    rules.allow(top, bottom, DOWN, UP);
+                }
+                _ => {
+                    return Err(WfcRulesError::UnknownDirective {
+                        line,
+                        found: directive.to_string(),
+                    });
+                }
+            }
+            if fields.next().is_some() {
+                return Err(WfcRulesError::TrailingText { line });
+            }
+        }
+        if rules.symbols.is_empty() {
+            return Err(WfcRulesError::NoTiles);
+        }
+        Ok(rules)
+    }
+
+    /// Reads a rules file in the format described on [`WfcRules`].
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, WfcRulesError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(WfcRulesError::Io)?)
+    }
+
+    /// Every tile, in the order first mentioned.
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    /// The weight of `symbol`, or `None` if it isn't a tile.
+    pub fn weight(&self, symbol: char) -> Option<f32> {
+        self.index(symbol).map(|tile| self.weights[tile])
+    }
+
+    /// `true` if `right` may sit directly to the right of `left`.
+    pub fn allows_horizontal(&self, left: char, right: char) -> bool {
+        self.allows(left, right, RIGHT)
+    }
+
+    /// `true` if `bottom` may sit directly below `top`.
+    pub fn allows_vertical(&self, top: char, bottom: char) -> bool {
+        self.allows(top, bottom, DOWN)
+    }
+
+    fn allows(&self, from: char, to: char, direction: usize) -> bool {
+        match (self.index(from), self.index(to)) {
+            (Some(from), Some(to)) => self.allowed[direction][from] & bit(to) != 0,
+            _ => false,
+        }
+    }
+
+    fn index(&self, symbol: char) -> Option<usize> {
+        self.symbols.iter().position(|known| *known == symbol)
+    }
+
+    /// The index of `symbol`, adding it with weight 1 if it is new. `None`
+    /// if there is no room for another tile.
+    fn add(&mut self, symbol: char) -> Option<usize> {
+        if let Some(tile) = self.index(symbol) {
+            return Some(tile);
+        }
+        if self.symbols.len() == MAX_TILES {
+            return None;
+        }
+        self.symbols.push(symbol);
+        self.weights.push(1.0);
+        self.allowed.iter_mut().for_each(|allowed| allowed.push(0));
+        Some(self.symbols.len() - 1)
+    }
+
+    fn add_or_panic(&mut self, symbol: char) -> usize {
+        self.add(symbol)
+            .unwrap_or_else(|| panic!("WfcRules: more than {MAX_TILES} different tiles"))
+    }
+
+    fn allow(&mut self, from: usize, to: usize, direction: usize, back: usize) {
+        self.allowed[direction][from] |= bit(to);
+        self.allowed[back][to] |= bit(from);
+    }
+
+    fn all_tiles(&self) -> Mask {
+        match self.symbols.len() {
+            MAX_TILES => Mask::MAX,
+            count => bit(count) - 1,
+        }
+    }
+}
+
+fn bit(tile: usize) -> Mask {
+    1 << tile
+}
+
+/// `TileGrid` is a rectangle of tile symbols filled in by a
+/// [`WaveFunctionCollapse`], addressed by `IVec2` positions from `(0, 0)`
+/// (top left) to `(width - 1, height - 1)`.
+///
+/// It can be stored as a resource or a component.
+#[derive(Clone, Debug, PartialEq, Eq, Resource, Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileGrid {
+    width: i32,
+    height: i32,
+    tiles: Vec<char>,
+}
+
+impl TileGrid {
+    /// Number of columns.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The tile at `pos`, or `None` off the grid.
+    pub fn tile(&self, pos: IVec2) -> Option<char> {
+        let on_grid = (0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y);
+        on_grid.then(|| self.tiles[(pos.y * self.width + pos.x) as usize])
+    }
+
+    /// Converts to a [`GridMap`], with floor wherever `is_floor` says so.
+    /// Unlike the [`MapGenerator`](crate::MapGenerator)s, nothing makes the
+    /// floor connected; [`GridMap::keep_largest_region`] can.
+    pub fn to_grid_map(&self, is_floor: impl Fn(char) -> bool) -> GridMap {
+        let mut map = GridMap::new(self.width, self.height, MapTile::Wall);
+        for (i, tile) in self.tiles.iter().enumerate() {
+            if is_floor(*tile) {
+                let pos = IVec2::new(i as i32 % self.width, i as i32 / self.width);
+                map.set(pos, MapTile::Floor);
+            }
+        }
+        map
+    }
+}
+
+/// Draws the grid one row per line, top row first.
+impl fmt::Display for TileGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.tiles.chunks(self.width as usize) {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+/// `WaveFunctionCollapse` fills a grid following [`WfcRules`]. It
+/// repeatedly picks the most constrained cell, settles it on a random tile
+/// (by weight), and rules out whatever that makes impossible in the cells
+/// around it. If a cell is left with no possible tile, it backtracks: it
+/// undoes the latest choice and tries another tile instead. Tangled rules
+/// can send backtracking down a long dead end, so after too many
+/// backtracks it starts over.
+///
+/// Every choice is drawn from a [`RandomSource`], so the same seed always
+/// gives the same grid.
+///
+/// ## Example
+///
+/// ```
+/// use my_library::{RandomNumberGenerator, WaveFunctionCollapse, WfcRules};
+/// // land next to coast next to sea, never land next to sea
+/// let rules = WfcRules::parse(
+///     "
+///     tile . 4
+///     tile , 1
+///     tile ~ 4
+///     h . .
+///     h . ,
+///     h , .
+///     h , ,
+///     h , ~
+///     h ~ ,
+///     h ~ ~
+///     v . .
+///     v . ,
+///     v , .
+///     v , ,
+///     v , ~
+///     v ~ ,
+///     v ~ ~
+///     ",
+/// )
+/// .unwrap();
+/// let mut rng = RandomNumberGenerator::seeded(1);
+/// let island = WaveFunctionCollapse::new(rules).generate(&mut rng, 40, 20).unwrap();
+/// println!("{island}");
+/// ```
+#[derive(Clone, Debug)]
+pub struct WaveFunctionCollapse {
+    rules: WfcRules,
+    max_backtracks: u32,
+}
+
+impl WaveFunctionCollapse {
+    /// Creates a generator for `rules`, which starts over after 1,000
+    /// backtracks.
+    pub fn new(rules: WfcRules) -> Self {
+        Self {
+            rules,
+            max_backtracks: 1000,
+        }
+    }
+
+    /// Sets how many choices one attempt may undo before starting over.
+    /// Zero never backtracks: any contradiction starts over.
+    pub fn max_backtracks(mut self, max_backtracks: u32) -> Self {
+        self.max_backtracks = max_backtracks;
+        self
+    }
+
+    /// The rules this generator follows.
+    pub fn rules(&self) -> &WfcRules {
+        &self.rules
+    }
+
+    /// Fills a `width` by `height` grid. An attempt that runs out of
+    /// backtracks starts over, up to 10 times. Returns `None` if the rules
+    /// can't fill a grid of this size, or every attempt ran out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is not positive.
+    pub fn generate(
+        &self,
+        mut rng: impl RandomSource,
+        width: i32,
+        height: i32,
+    ) -> Option<TileGrid> {
+        assert!(
+            width > 0 && height > 0,
+            "WaveFunctionCollapse: width and height must be positive"
+        );
+        for _ in 0..MAX_ATTEMPTS {
+            match self.attempt(&mut rng, width, height) {
+                Attempt::Solved(grid, backtracks) => {
+                    debug!("wave function collapse backtracked {backtracks} times");
+                    return Some(grid);
+                }
+                Attempt::Impossible => return None,
+                Attempt::GaveUp => {}
+            }
+        }
+        None
+    }
+
+    fn attempt(&self, rng: &mut impl RandomSource, width: i32, height: i32) -> Attempt {
+        let mut wave = Wave::new(&self.rules, width, height, rng);
+        if !wave.propagate((0..wave.cells.len()).collect()) {
+            return Attempt::Impossible;
+        }
+
+        let mut choices: Vec<Choice> = Vec::new();
+        let mut backtracks = 0;
+        while let Some(cell) = wave.most_constrained() {
+            let options = wave.options(cell);
+            // every weight is positive, so this always picks one
+            let tile = *rng
+                .choose_weighted(&options, |tile| self.rules.weights[*tile])
+                .unwrap();
+            choices.push(Choice {
+                cell,
+                tile,
+                trail: wave.trail.len(),
+            });
+            wave.set(cell, bit(tile));
+            let mut consistent = wave.propagate(vec![cell]);
+            while !consistent {
+                // with nothing left to undo, no grid is possible
+                let Some(choice) = choices.pop() else {
+                    return Attempt::Impossible;
+                };
+                if backtracks == self.max_backtracks {
+                    return Attempt::GaveUp;
+                }
+                backtracks += 1;
+                wave.undo(choice.trail);
+                let remaining = wave.cells[choice.cell] & !bit(choice.tile);
+                wave.set(choice.cell, remaining);
+                consistent = remaining != 0 && wave.propagate(vec![choice.cell]);
+            }
+        }
+
+        let tiles = wave
+            .cells
+            .iter()
+            .map(|cell| self.rules.symbols[cell.trailing_zeros() as usize])
+            .collect();
+        let grid = TileGrid {
+            width,
+            height,
+            tiles,
+        };
+        Attempt::Solved(grid, backtracks)
+    }
+}
+
+/// How one attempt at filling the grid ended.
+enum Attempt {
+    /// The grid, and how many times it backtracked.
+    Solved(TileGrid, u32),
+    /// No choices were left to undo: no grid of this size is possible.
+    Impossible,
+    /// It ran out of backtracks.
+    GaveUp,
+}
+
+/// A tile picked for a cell, and where the trail stood before picking it.
+struct Choice {
+    cell: usize,
+    tile: usize,
+    trail: usize,
+}
+
+/// The tiles every cell could still become.
+struct Wave<'a> {
+    rules: &'a WfcRules,
+    width: i32,
+    height: i32,
+    cells: Vec<Mask>,
+    // every change made, as (cell, previous tiles), so it can be undone
+    trail: Vec<(usize, Mask)>,
+    // unsettled cells, lowest entropy first, as (entropy, tie break, cell,
+    // tiles); an entry is stale once the cell's tiles have changed
+    queue: BinaryHeap<Reverse<(i64, u32, usize, Mask)>>,
+    // a random order for cells of equal entropy, fixed for the attempt
+    tie_breaks: Vec<u32>,
+}
+
+impl<'a> Wave<'a> {
+    fn new(rules: &'a WfcRules, width: i32, height: i32, rng: &mut impl RandomSource) -> Self {
+        let len = (width * height) as usize;
+        let mut tie_breaks: Vec<u32> = (0..len as u32).collect();
+        rng.shuffle(&mut tie_breaks);
+        let mut wave = Self {
+            rules,
+            width,
+            height,
+            cells: vec![rules.all_tiles(); len],
+            trail: Vec::new(),
+            queue: BinaryHeap::with_capacity(len),
+            tie_breaks,
+        };
+        (0..len).for_each(|cell| wave.enqueue(cell));
+        wave
+    }
+
+    fn set(&mut self, cell: usize, tiles: Mask) {
+        self.trail.push((cell, self.cells[cell]));
+        self.cells[cell] = tiles;
+        self.enqueue(cell);
+    }
+
+    fn undo(&mut self, trail: usize) {
+        while self.trail.len() > trail {
+            let (cell, tiles) = self.trail.pop().unwrap();
+            self.cells[cell] = tiles;
+            self.enqueue(cell);
+        }
+    }
+
+    /// Queues `cell` with its current tiles, unless it is settled.
+    fn enqueue(&mut self, cell: usize) {
+        let tiles = self.cells[cell];
+        if tiles.count_ones() > 1 {
+            // entropies within 1e-4 of each other count as a tie
+            let entropy = (self.entropy(tiles) * 1e4).round() as i64;
+            self.queue
+                .push(Reverse((entropy, self.tie_breaks[cell], cell, tiles)));
+        }
+    }
+
+    fn options(&self, cell: usize) -> Vec<usize> {
+        tiles(self.cells[cell]).collect()
+    }
+
+    fn neighbor(&self, cell: usize, direction: usize) -> Option<usize> {
+        let pos = IVec2::new(cell as i32 % self.width, cell as i32 / self.width);
+        let next = pos + DIRECTIONS[direction];
+        let on_grid = (0..self.width).contains(&next.x) && (0..self.height).contains(&next.y);
+        on_grid.then(|| (next.y * self.width + next.x) as usize)
+    }
+
+    /// Rules out tiles that no longer fit next to the cells in `open`, and
+    /// everything that follows from that. `false` if a cell is left with no
+    /// tile.
+    fn propagate(&mut self, mut open: Vec<usize>) -> bool {
+        while let Some(cell) = open.pop() {
+            for direction in 0..DIRECTIONS.len() {
+                let Some(next) = self.neighbor(cell, direction) else {
+                    continue;
+                };
+                let allowed = tiles(self.cells[cell]).fold(0, |allowed, tile| {
+                    allowed | self.rules.allowed[direction][tile]
+                });
+                let remaining = self.cells[next] & allowed;
+                if remaining != self.cells[next] {
+                    if remaining == 0 {
+                        return false;
+                    }
+                    self.set(next, remaining);
+                    open.push(next);
+                }
+            }
+        }
+        true
+    }
+
+    /// The unsettled cell with the lowest entropy (ties broken at random),
+    /// or `None` once every cell is settled.
+    fn most_constrained(&mut self) -> Option<usize> {
+        while let Some(Reverse((_, _, cell, tiles))) = self.queue.pop() {
+            if self.cells[cell] == tiles {
+                // it is settled next, and queued again if that is undone
+                return Some(cell);
+            }
+        }
+        None
+    }
+
+    /// Shannon entropy of the weights of the tiles in `mask`.
+    fn entropy(&self, mask: Mask) -> f32 {
+        let (total, weighted_logs) =
+            tiles(mask).fold((0.0, 0.0), |(total, weighted_logs), tile| {
+                let weight = self.rules.weights[tile];
+                (total + weight, weighted_logs + weight * weight.ln())
+            });
+        total.ln() - weighted_logs / total
+    }
+}
+
+/// The tiles in `mask`, lowest first.
+fn tiles(mut mask: Mask) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (mask != 0).then(|| {
+            let tile = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            tile
+        })
+    })
+}
+
+#[cfg(test)]
+// tests pass `&mut rng` so they build with and without the `locking` feature
+#[cfg_attr(feature = "locking", allow(clippy::unnecessary_mut_passed))]
+mod test {
+    use super::*;
+    use crate::{Algorithm, RandomNumberGenerator};
+
+    const EXAMPLE: &str = "
+        ~~~~~~~~~~
+        ~~,,,,,~~~
+        ~,,...,,~~
+        ~,.....,,~
+        ~,,...,,~~
+        ~~,,,,,~~~
+        ~~~~~~~~~~
+    ";
+
+    /// Checks that every pair of neighbors in `grid` is allowed.
+    fn assert_follows(rules: &WfcRules, grid: &TileGrid) {
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let here = grid.tile(IVec2::new(x, y)).unwrap();
+                if let Some(right) = grid.tile(IVec2::new(x + 1, y)) {
+                    assert!(
+                        rules.allows_horizontal(here, right),
+                        "{here}{right}\n{grid}"
+                    );
+                }
+                if let Some(below) = grid.tile(IVec2::new(x, y + 1)) {
+                    assert!(rules.allows_vertical(here, below), "{here}/{below}\n{grid}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_example() {
+        let rules = WfcRules::from_example(EXAMPLE).unwrap();
+        assert_eq!(rules.symbols(), ['~', ',', '.']);
+        assert_eq!(rules.weight('.'), Some(11.0));
+        assert!(rules.allows_horizontal(',', '.') && rules.allows_vertical('~', ','));
+        assert!(!rules.allows_horizontal('~', '.') && !rules.allows_vertical('.', '~'));
+        assert!(!rules.allows_horizontal('~', '#'));
+        assert!(matches!(
+            WfcRules::from_example("\n  \n"),
+            Err(WfcRulesError::NoTiles)
+        ));
+
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let grid = WaveFunctionCollapse::new(rules.clone())
+            .generate(&mut rng, 30, 20)
+            .unwrap();
+        assert_eq!((grid.width(), grid.height()), (30, 20));
+        assert_follows(&rules, &grid);
+    }
+
+    #[test]
+    fn test_parse() {
+        let rules = WfcRules::parse(
+            "# water and sand
+            tile ~ 3
+            tile .
+            h ~ .
+            v . ~  ",
+        )
+        .unwrap();
+        assert_eq!(rules.symbols(), ['~', '.']);
+        assert_eq!(
+            (rules.weight('~'), rules.weight('.')),
+            (Some(3.0), Some(1.0))
+        );
+        assert!(rules.allows_horizontal('~', '.') && !rules.allows_horizontal('.', '~'));
+        assert!(rules.allows_vertical('.', '~') && !rules.allows_vertical('~', '.'));
+        // a `#` tile is fine; only lines starting with `#` are comments
+        assert!(
+            WfcRules::parse("h # #")
+                .unwrap()
+                .allows_horizontal('#', '#')
+        );
+
+        let built = WfcRules::new()
+            .tile('~', 3.0)
+            .tile('.', 1.0)
+            .horizontal('~', '.')
+            .vertical('.', '~');
+        assert_eq!(rules, built);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text| WfcRules::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("tile . 1\nhv . ."),
+            "line 2: unknown directive \"hv\""
+        );
+        assert_eq!(
+            error("\n\nh .. ."),
+            "line 3: \"..\" is not a single-character tile"
+        );
+        assert_eq!(error("v ."), "line 1: \"\" is not a single-character tile");
+        assert_eq!(
+            error("tile . -1"),
+            "line 1: \"-1\" is not a positive weight"
+        );
+        assert_eq!(
+            error("tile . heavy"),
+            "line 1: \"heavy\" is not a positive weight"
+        );
+        assert_eq!(error("h . . ."), "line 1: unexpected text at the end");
+        assert_eq!(error("# nothing\n"), "no tiles defined");
+        let too_many: String = (0..=MAX_TILES as u32)
+            .map(|i| format!("tile {}\n", char::from_u32(0x100 + i).unwrap()))
+            .collect();
+        assert_eq!(error(&too_many), "line 129: more than 128 different tiles");
+
+        let path = std::env::temp_dir().join("my_library_test_missing.wfc");
+        assert!(matches!(
+            WfcRules::from_file(path),
+            Err(WfcRulesError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join("my_library_test_rules.wfc");
+        std::fs::write(&path, "tile x 2\nh x x\nv x x\n").unwrap();
+        let rules = WfcRules::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let grid = WaveFunctionCollapse::new(rules.unwrap())
+            .generate(RandomNumberGenerator::seeded(1), 3, 2)
+            .unwrap();
+        assert_eq!(grid.to_string(), "xxx\nxxx\n");
+    }
+
+    #[test]
+    fn test_seeded() {
+        let generator = WaveFunctionCollapse::new(WfcRules::from_example(EXAMPLE).unwrap());
+        let grid = generator.generate(RandomNumberGenerator::seeded(1), 20, 20);
+        let again = generator.generate(RandomNumberGenerator::seeded(1), 20, 20);
+        let other = generator.generate(RandomNumberGenerator::seeded(2), 20, 20);
+        assert!(grid.is_some());
+        assert_eq!(grid, again);
+        assert_ne!(grid, other);
+    }
+
+    #[test]
+    fn test_large_grid() {
+        let rules = WfcRules::from_example(EXAMPLE).unwrap();
+        let generator = WaveFunctionCollapse::new(rules.clone());
+        let grid = generator
+            .generate(RandomNumberGenerator::seeded(3), 200, 200)
+            .unwrap();
+        assert_follows(&rules, &grid);
+    }
+
+    #[test]
+    fn test_weights() {
+        // anything goes next to anything, so only the weights matter
+        let mut rules = WfcRules::new().tile('a', 9.0).tile('b', 1.0);
+        for (x, y) in [('a', 'a'), ('a', 'b'), ('b', 'a'), ('b', 'b')] {
+            rules = rules.horizontal(x, y).vertical(x, y);
+        }
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let grid = WaveFunctionCollapse::new(rules)
+            .generate(&mut rng, 50, 40)
+            .unwrap();
+        let share = grid.tiles.iter().filter(|tile| **tile == 'a').count() as f32 / 2000.0;
+        assert!((0.85..0.95).contains(&share), "{share}");
+    }
+
+    #[test]
+    fn test_impossible() {
+        // tiles may only sit side by side, never stacked
+        let rules = WfcRules::new().horizontal('-', '-');
+        let generator = WaveFunctionCollapse::new(rules);
+        let mut rng = RandomNumberGenerator::seeded(4);
+        assert_eq!(
+            generator.generate(&mut rng, 5, 1).unwrap().to_string(),
+            "-----\n"
+        );
+        assert_eq!(generator.generate(&mut rng, 5, 2), None);
+    }
+
+    // Coloring the grid with three colors, no two neighbors alike: choices
+    // that look fine locally can corner a cell later, so some seeds need
+    // backtracking.
+    fn hard_rules() -> WfcRules {
+        let mut rules = WfcRules::new();
+        for a in ['r', 'g', 'b'] {
+            for b in ['r', 'g', 'b'] {
+                if a != b {
+                    rules = rules.horizontal(a, b).vertical(a, b);
+                }
+            }
+        }
+        rules
+    }
+
+    #[test]
+    fn test_backtracking() {
+        let rules = hard_rules();
+        let generator = WaveFunctionCollapse::new(rules.clone());
+        let strict = generator.clone().max_backtracks(0);
+        let limited = generator.clone().max_backtracks(5);
+        let (mut backtracked, mut gave_up) = (false, false);
+        // every algorithm, so the seeds tried don't depend on features
+        for algorithm in Algorithm::ALL {
+            for seed in 0..20 {
+                let rng = || RandomNumberGenerator::with_algorithm(algorithm, seed);
+                match generator.attempt(&mut rng(), 12, 12) {
+                    Attempt::Solved(grid, backtracks) if backtracks > 0 => {
+                        backtracked = true;
+                        assert_follows(&rules, &grid);
+                        // without backtracking, the same choices fail
+                        assert!(matches!(
+                            strict.attempt(&mut rng(), 12, 12),
+                            Attempt::GaveUp
+                        ));
+                    }
+                    Attempt::GaveUp => gave_up = true,
+                    _ => {}
+                }
+                if matches!(limited.attempt(&mut rng(), 12, 12), Attempt::GaveUp) {
+                    gave_up = true;
+                }
+                // starting over gets past any dead end
+                let grid = generator.generate(rng(), 12, 12).unwrap();
+                assert_follows(&rules, &grid);
+            }
+        }
+        assert!(backtracked && gave_up);
+    }
+
+    #[test]
+    fn test_to_grid_map() {
+        let rules = WfcRules::from_example(EXAMPLE).unwrap();
+        let mut rng = RandomNumberGenerator::seeded(5);
+        let grid = WaveFunctionCollapse::new(rules)
+            .generate(&mut rng, 20, 10)
+            .unwrap();
+        let map = grid.to_grid_map(|tile| tile != '~');
+        let land = grid.tiles.iter().filter(|tile| **tile != '~').count();
+        assert_eq!(map.floor_count(), land);
+        assert_eq!(
+            map.is_floor(IVec2::new(3, 4)),
+            grid.tile(IVec2::new(3, 4)) != Some('~')
+        );
+    }
+}
@@ -1,10 +1,15 @@
 use bevy::prelude::*;
 use bevy_egui::{EguiContexts, EguiPlugin, egui};
-use my_library::{GameStatePlugin, RandomNumberGenerator, RandomPlugin, add_phase, cleanup};
+use my_library::{
+    AssetManager, AssetStore, GameStatePlugin, RandomPlugin, RandomSource, SystemRng, add_phase,
+    anyhow, cleanup,
+};
 
 // Vincent: States is specificially for state machine view of games
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default, States)]
 enum GamePhase {
+    Loading,
+    LoadFailed,
     #[default]
     MainMenu,
     Start,
@@ -17,12 +22,6 @@ enum GamePhase {
 #[derive(Component)]
 pub struct GameElement;
 
-#[derive(Resource)]
-struct GameAssets {
-    image: Handle<Image>,
-    layout: Handle<TextureAtlasLayout>,
-}
-
 #[derive(Clone, Copy, Resource)]
 struct Scores {
     player: usize,
@@ -46,8 +45,8 @@ fn check_game_over(mut state: ResMut<NextState<GamePhase>>, scores: Res<Scores>)
 
 fn display_final_score(scores: Res<FinalScore>, mut egui_context: EguiContexts) {
     egui::Window::new("Total Scores").show(egui_context.ctx_mut(), |ui| {
-        ui.label(&format!("Player: {}", scores.0.player));
-        ui.label(&format!("CPU: {}", scores.0.cpu));
+        ui.label(format!("Player: {}", scores.0.player));
+        ui.label(format!("CPU: {}", scores.0.cpu));
         if scores.0.player < scores.0.cpu {
             ui.label("CPU wins!");
         } else {
@@ -66,20 +65,8 @@ struct HandDie;
 // Vincent: dit is omdat we niet meteen elke dobbelsteen tegelijk willen rollen voor CPU
 struct HandTimer(Timer);
 
-fn setup(
-    asset_server: Res<AssetServer>,
-    mut commands: Commands,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    commands.spawn(Camera2d::default()).insert(GameElement);
-    let texture = asset_server.load("dice.png");
-    // Vincent: 6 vierkantjes met zijden van 52 pixels
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(52), 6, 1, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    commands.insert_resource(GameAssets {
-        image: texture,
-        layout: texture_atlas_layout,
-    });
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2d).insert(GameElement);
     commands.insert_resource(Scores { cpu: 0, player: 0 });
     // commands.insert_resource(Random(RandomNumberGenerator::new()));
     commands.insert_resource(HandTimer(Timer::from_seconds(1.0, TimerMode::Repeating)));
@@ -87,8 +74,8 @@ fn setup(
 
 fn display_score(scores: Res<Scores>, mut egui_context: EguiContexts) {
     egui::Window::new("Total Scores").show(egui_context.ctx_mut(), |ui| {
-        ui.label(&format!("Player: {}", scores.player));
-        ui.label(&format!("CPU: {}", scores.cpu));
+        ui.label(format!("Player: {}", scores.player));
+        ui.label(format!("CPU: {}", scores.cpu));
     });
 }
 
@@ -98,18 +85,12 @@ fn display_score(scores: Res<Scores>, mut egui_context: EguiContexts) {
 fn spawn_die(
     hand_query: &Query<(Entity, &Sprite), With<HandDie>>,
     commands: &mut Commands,
-    assets: &GameAssets,
+    assets: &AssetStore,
     new_roll: usize,
     color: Color,
 ) {
     let rolled_die = hand_query.iter().count() as f32 * 52.0;
-    let mut sprite = Sprite::from_atlas_image(
-        assets.image.clone(),
-        TextureAtlas {
-            layout: assets.layout.clone(),
-            index: new_roll - 1,
-        },
-    );
+    let mut sprite = assets.sprite_sheet("dice").unwrap().sprite(new_roll - 1);
     sprite.color = color;
     commands.spawn((
         sprite,
@@ -134,8 +115,8 @@ fn clear_die(hand_query: &Query<(Entity, &Sprite), With<HandDie>>, commands: &mu
 fn player(
     hand_query: Query<(Entity, &Sprite), With<HandDie>>,
     mut commands: Commands,
-    rng: Res<RandomNumberGenerator>,
-    assets: Res<GameAssets>,
+    mut rng: SystemRng,
+    assets: Res<AssetStore>,
     mut scores: ResMut<Scores>,
     mut state: ResMut<NextState<GamePhase>>,
     mut egui_context: EguiContexts,
@@ -146,9 +127,9 @@ fn player(
             .iter()
             .map(|(_, ts)| ts.texture_atlas.as_ref().unwrap().index + 1)
             .sum();
-        ui.label(&format!("Score for this hand: {hand_score}"));
+        ui.label(format!("Score for this hand: {hand_score}"));
         if ui.button("Roll Dice").clicked() {
-            let new_roll = rng.range(1..=6);
+            let new_roll = rng.get("player").range(1..=6);
             if new_roll == 1 {
                 clear_die(&hand_query, &mut commands);
                 state.set(GamePhase::Cpu);
@@ -173,9 +154,9 @@ fn cpu(
     hand_query: Query<(Entity, &Sprite), With<HandDie>>,
     mut state: ResMut<NextState<GamePhase>>,
     mut scores: ResMut<Scores>,
-    rng: Res<RandomNumberGenerator>,
+    mut rng: SystemRng,
     mut commands: Commands,
-    assets: Res<GameAssets>,
+    assets: Res<AssetStore>,
     mut timer: ResMut<HandTimer>,
     time: Res<Time>,
 ) {
@@ -187,7 +168,7 @@ fn cpu(
             .sum();
         // Vincent: CPU mikt dus op 20 of hoger en wil in totaal score van 100 halen
         if hand_total < 20 && scores.cpu + hand_total < 100 {
-            let new_roll = rng.range(1..=6);
+            let new_roll = rng.get("cpu").range(1..=6);
             if new_roll == 1 {
                 clear_die(&hand_query, &mut commands);
                 state.set(GamePhase::Player);
@@ -210,7 +191,7 @@ fn cpu(
     }
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let mut app = App::new();
     add_phase!(app, GamePhase, GamePhase::Start, start => [ setup ], run => [ start_game ], exit => [ ]);
     add_phase!(app, GamePhase, GamePhase::Player, start => [], run => [ player, check_game_over, display_score ], exit => [ ]);
@@ -225,15 +206,21 @@ fn main() {
         }),
         ..default()
     }))
-    .add_plugins(GameStatePlugin::new(
-        GamePhase::MainMenu,
-        GamePhase::Start,
-        GamePhase::GameOver,
-    ))
-    .add_plugins(RandomPlugin)
+    .add_plugins(
+        GameStatePlugin::new(GamePhase::MainMenu, GamePhase::Start, GamePhase::GameOver)
+            .with_loading(GamePhase::Loading, GamePhase::LoadFailed),
+    )
+    .add_plugins(RandomPlugin::from_env("GAME_SEED"))
     .add_plugins(EguiPlugin {
         enable_multipass_for_primary_context: false,
     })
+    .add_plugins(
+        // Vincent: 6 vierkantjes met zijden van 52 pixels
+        AssetManager::new()
+            .add_sprite_sheet("dice", "dice.png", UVec2::splat(52), 6, 1)
+            .validate()?,
+    )
     .add_systems(Startup, setup)
     .run();
+    Ok(())
 }